@@ -0,0 +1,94 @@
+// Throughput of the pieces of breadth-first search, and time to solution
+// for x - y. Run with `cargo bench`. The full search is also timed on one
+// thread in a group of its own, as a baseline without parallelism.
+
+#[macro_use]
+extern crate criterion;
+#[macro_use]
+extern crate vasm_sim;
+use vasm_sim::{add_one_instruction, execute, verify, Execution, Instruction, Program, Register, SynthesizerBuilder};
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+
+use std::hint::black_box;
+
+// Nine test cases, the size of the demo spec
+fn inputs() -> Vec<Vec<isize>> {
+    vec![
+        vec![0, 0, 3, -1],
+        vec![0, 1, 3, -1],
+        vec![1, 1, 3, -1],
+        vec![1, 0, 3, -1],
+        vec![0, -1, 3, -1],
+        vec![-1, 0, 3, -1],
+        vec![-1, -1, 3, -1],
+        vec![1, -1, 3, -1],
+        vec![-1, 1, 3, -1],
+    ]
+}
+
+// Programs per second, counting every child of the 4-register root
+fn bench_add_one_instruction(c: &mut Criterion) {
+    let root = Execution::root(inputs());
+    let mut group = c.benchmark_group("add_one_instruction");
+    group.throughput(Throughput::Elements(add_one_instruction(&root).len() as u64));
+    group.bench_function("4 registers", |b| b.iter(|| add_one_instruction(black_box(&root))));
+    group.finish();
+}
+
+fn bench_execute(c: &mut Criterion) {
+    let root = Execution::root(inputs());
+    let (r0, r1) = (Register::new(0), Register::new(1));
+    let mut group = c.benchmark_group("execute");
+    group.throughput(Throughput::Elements(1));
+    for inst in [Instruction::Mov(r0, r1), Instruction::Add(r0, r1), Instruction::Mul(r0, r1), Instruction::Neg(r0)] {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{:?}", inst.variant())), &inst, |b, inst| {
+            b.iter(|| {
+                execute(Program {
+                    parent: Some(&root),
+                    instruction: Some(inst.clone()),
+                    depth: 1,
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let root = Execution::root(inputs());
+    let sum = execute(Program {
+        parent: Some(&root),
+        instruction: Some(Instruction::Add(Register::new(0), Register::new(1))),
+        depth: 1,
+    });
+    let outputs = sum.output.iter().map(|row| row[0]).collect::<Vec<_>>();
+    let mut group = c.benchmark_group("verify");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("9 test cases", |b| b.iter(|| verify(black_box(&sum), &outputs)));
+    group.finish();
+}
+
+// Wall time to solution, one solved search per iteration
+fn bench_search(c: &mut Criterion, group_name: &str, builder: SynthesizerBuilder) {
+    let difference = testcases![([1, 2], -1), ([4, -1], 5), ([0, 5], -5), ([2, 2], 0), ([-3, 4], -7),];
+    let synthesizer = builder.build(&difference);
+    let mut group = c.benchmark_group(group_name);
+    group.throughput(Throughput::Elements(1));
+    group.sample_size(10);
+    group.bench_function("x - y", |b| {
+        b.iter(|| assert!(synthesizer.run().unwrap().is_some(), "x - y was not solved"))
+    });
+    group.finish();
+}
+
+fn bench_bfs(c: &mut Criterion) {
+    bench_search(c, "bfs", SynthesizerBuilder::new());
+}
+
+fn bench_bfs_one_thread(c: &mut Criterion) {
+    bench_search(c, "bfs, 1 thread", SynthesizerBuilder::new().with_threads(1));
+}
+
+criterion_group!(benches, bench_add_one_instruction, bench_execute, bench_verify, bench_bfs, bench_bfs_one_thread);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+// The sum of five inputs needs four instructions, which puts it several
+// generations away from the empty program for breadth-first search, but each
+// correct `add` brings the score closer so MCMC walks straight to it.
+
+#[macro_use]
+extern crate vasm_sim;
+use vasm_sim::{CancellationToken, MCMCSearchStrategy, SearchStrategy, SynthesizerBuilder};
+
+use std::time::Instant;
+
+fn main() {
+    let spec = testcases![
+        ([1, 2, 3, 4, 5], 15),
+        ([3, 1, 4, 1, 5], 14),
+        ([2, 7, 1, 8, 2], 20),
+        ([9, 0, 2, 6, 5], 22),
+        ([5, 3, 5, 8, 9], 30),
+        ([1, 1, 2, 3, 5], 12),
+    ];
+
+    let start = Instant::now();
+    let mcmc = MCMCSearchStrategy::new(5, 1_000_000);
+    match mcmc.run(&spec, CancellationToken::new()) {
+        Some((exe, reg)) => println!("MCMC found in {:?}:{}\noutput in r{}", start.elapsed(), exe, reg),
+        None => println!("MCMC gave up after {:?}", start.elapsed()),
+    }
+
+    // Depth 4 takes minutes and gigabytes, so stop one short of it
+    let start = Instant::now();
+    let bfs = SynthesizerBuilder::new().with_max_depth(3).build(&spec);
+    match bfs.run().unwrap() {
+        Some((exe, reg)) => println!("BFS found in {:?}:{}\noutput in r{}", start.elapsed(), exe, reg),
+        None => println!("BFS found nothing up to depth 3 after {:?}", start.elapsed()),
+    }
+}
@@ -0,0 +1,132 @@
+use indexmap::IndexSet;
+
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use arena::ExecutionArena;
+use memo::MemoTable;
+use synthesizer::Synthesizer;
+use {Execution, OwnedExecution, Register, RegisterIndex, Value};
+
+// Executions kept per generation of the beam search, unless the builder
+// sets a beam width
+const BEAM_WIDTH: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct ApproximateResult {
+    pub execution: OwnedExecution,
+    pub register: RegisterIndex,
+    // Test cases where `register` holds the expected output
+    pub satisfied_cases: usize,
+}
+
+// The register matching the most test cases, and how many it matches.
+// Ties go to `hint`, then to the lowest register.
+fn best_register(output: &[Vec<Value>], tests: &[Value], hint: Option<RegisterIndex>) -> (RegisterIndex, usize) {
+    (0..output[0].len())
+        .map(|reg| {
+            let satisfied = output.iter().zip(tests).filter(|&(row, test)| row[reg] == *test).count();
+            (Register::new(reg), satisfied)
+        })
+        .max_by_key(|&(reg, satisfied)| (satisfied, Some(reg) == hint, Reverse(reg)))
+        .unwrap()
+}
+
+// Target values `reg` holds on some test case, not necessarily the right
+// one, so a program that has computed them in the wrong order still counts
+// as close. 0 if the program has no such register.
+fn intersection_score(exe: &Execution, targets: &HashSet<Value>, reg: RegisterIndex) -> usize {
+    if reg.as_index() >= exe.output[0].len() {
+        return 0;
+    }
+    exe.reachable_outputs(reg).intersection(targets).count()
+}
+
+impl Synthesizer {
+    // Beam search for `budget` generations, keeping the programs that satisfy
+    // the most test cases, and among those the ones whose output register
+    // hint, or best register without one, reaches the most target values.
+    // Returns the best program seen, which solves the spec outright if
+    // satisfied_cases equals the number of cases. Each generation is
+    // expanded and filtered as the search's would be.
+    pub fn run_approximate(&self, budget: usize) -> ApproximateResult {
+        self.config.install(|| {
+            let arena = ExecutionArena::new();
+            let memo = MemoTable::new();
+            let start = self.start(&arena);
+            let hint = self.config.output_register_hint;
+            let targets = self.outputs.iter().cloned().collect::<HashSet<_>>();
+            let (register, satisfied_cases) = best_register(&start.output, &self.outputs, hint);
+            let mut best = ApproximateResult {
+                execution: OwnedExecution::from(&start),
+                register,
+                satisfied_cases,
+            };
+
+            let mut starts = IndexSet::new();
+            starts.insert(start);
+            let mut beam = arena.alloc_generation(starts);
+
+            for _ in 0..budget {
+                if best.satisfied_cases == self.outputs.len() {
+                    break;
+                }
+
+                let candidates = self.next_generation(beam, &memo);
+                if candidates.is_empty() {
+                    break;
+                }
+
+                let mut ranked = candidates
+                    .into_iter()
+                    .map(|exe| (best_register(&exe.output, &self.outputs, hint), exe))
+                    .collect::<Vec<_>>();
+                ranked.sort_by_cached_key(|&((reg, satisfied), ref exe)| {
+                    (Reverse(satisfied), Reverse(intersection_score(exe, &targets, hint.unwrap_or(reg))))
+                });
+                ranked.truncate(self.config.beam_width.unwrap_or(BEAM_WIDTH));
+
+                let ((register, satisfied_cases), ref leader) = ranked[0];
+                if satisfied_cases > best.satisfied_cases {
+                    best = ApproximateResult {
+                        execution: OwnedExecution::from(leader),
+                        register,
+                        satisfied_cases,
+                    };
+                }
+
+                beam = arena.alloc_generation(ranked.into_iter().map(|(_, exe)| exe).collect::<IndexSet<Execution>>());
+            }
+
+            best
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testsuite::{TestCase, TestSuite};
+    use {InstructionVariant, SynthesizerBuilder};
+
+    // Twice the input, except on the last case, which no two instructions
+    // get right along with the others
+    fn almost_double() -> TestSuite {
+        TestSuite::new(vec![TestCase::new(vec![1], 2), TestCase::new(vec![2], 4), TestCase::new(vec![3], 7)])
+    }
+
+    #[test]
+    fn beam_keeps_best_partial_solution() {
+        let result = SynthesizerBuilder::new().build(&almost_double()).run_approximate(2);
+        assert_eq!(result.satisfied_cases, 2);
+        assert_eq!(result.execution.instructions.len(), 1);
+    }
+
+    #[test]
+    fn beam_follows_enabled_instructions() {
+        let mul_neg = SynthesizerBuilder::new()
+            .with_enabled_instructions(vec![InstructionVariant::Mul, InstructionVariant::Neg]);
+        let result = mul_neg.build(&almost_double()).run_approximate(2);
+        assert_eq!(result.satisfied_cases, 1);
+        assert!(result.execution.instructions.iter().all(|inst| inst.variant() != InstructionVariant::Add));
+    }
+}
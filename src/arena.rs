@@ -0,0 +1,59 @@
+use typed_arena::Arena;
+
+use indexmap::IndexSet;
+
+use std::cell::Cell;
+use std::mem::size_of;
+
+use {Execution, Value};
+
+// Owns every generation of a search so executions can point at their parents
+pub struct ExecutionArena<'a> {
+    arena: Arena<IndexSet<Execution<'a>>>,
+    generations: Cell<usize>,
+    bytes: Cell<usize>,
+}
+
+impl<'a> Default for ExecutionArena<'a> {
+    fn default() -> ExecutionArena<'a> {
+        ExecutionArena::new()
+    }
+}
+
+impl<'a> ExecutionArena<'a> {
+    pub fn new() -> ExecutionArena<'a> {
+        ExecutionArena {
+            arena: Arena::new(),
+            generations: Cell::new(0),
+            bytes: Cell::new(0),
+        }
+    }
+
+    pub fn alloc_generation(&'a self, generation: IndexSet<Execution<'a>>) -> &'a IndexSet<Execution<'a>> {
+        self.generations.set(self.generations.get() + 1);
+        self.bytes.set(self.bytes.get() + generation_bytes(&generation));
+        self.arena.alloc(generation)
+    }
+
+    pub fn generation_count(&self) -> usize {
+        self.generations.get()
+    }
+
+    // Estimated from set capacities and output matrices at allocation time
+    pub fn total_allocated_bytes(&self) -> usize {
+        self.bytes.get()
+    }
+}
+
+pub fn execution_bytes(exe: &Execution) -> usize {
+    size_of::<Execution>()
+        + exe.output.capacity() * size_of::<Vec<Value>>()
+        + exe.output.iter().map(|row| row.capacity() * size_of::<Value>()).sum::<usize>()
+        + exe.ordering.capacity() * size_of::<usize>()
+}
+
+fn generation_bytes(generation: &IndexSet<Execution>) -> usize {
+    size_of::<IndexSet<Execution>>()
+        + (generation.capacity() - generation.len()) * size_of::<Execution>()
+        + generation.iter().map(execution_bytes).sum::<usize>()
+}
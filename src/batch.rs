@@ -0,0 +1,31 @@
+use rayon::prelude::*;
+
+use std::sync::mpsc;
+use std::thread;
+
+use synthesizer::{SynthesisResult, SynthesizerBuilder};
+use testsuite::TestSuite;
+
+pub type BatchResult = (usize, SynthesisResult);
+
+// Solve every spec in parallel. Results arrive in completion order, tagged
+// with the index of their spec.
+pub fn batch_synthesize(specs: Vec<TestSuite>, builder: &SynthesizerBuilder) -> impl Iterator<Item = BatchResult> {
+    let (sender, receiver) = mpsc::channel();
+    let builder = builder.clone();
+
+    thread::spawn(move || {
+        builder.install(|| {
+            specs
+                .into_par_iter()
+                .enumerate()
+                .for_each_with(sender, |sender, (index, spec)| {
+                    let result = builder.build(&spec).run();
+                    // Nobody to tell if the caller dropped the iterator
+                    let _ = sender.send((index, result));
+                });
+        })
+    });
+
+    receiver.into_iter()
+}
@@ -0,0 +1,336 @@
+use rayon::prelude::*;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use synthesizer::SynthesisError;
+use {Register, RegisterIndex};
+
+// A truth table packs one test case per bit, so a spec can have at most this
+// many cases
+pub const MAX_BOOLEAN_CASES: usize = 64;
+
+// Every instruction writes its result to a fresh register, leaving its
+// operands intact, so an execution never has to copy a value to keep it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoolInstruction {
+    And(RegisterIndex, RegisterIndex),
+    Or(RegisterIndex, RegisterIndex),
+    Xor(RegisterIndex, RegisterIndex),
+    Not(RegisterIndex),
+    Nand(RegisterIndex, RegisterIndex),
+    Nor(RegisterIndex, RegisterIndex),
+}
+
+impl BoolInstruction {
+    // The new register's column, with bits past the last test case left
+    // unmasked
+    fn eval(&self, registers: &[u64]) -> u64 {
+        let get = |r: RegisterIndex| registers[r.as_index()];
+        match *self {
+            BoolInstruction::And(r1, r2) => get(r1) & get(r2),
+            BoolInstruction::Or(r1, r2) => get(r1) | get(r2),
+            BoolInstruction::Xor(r1, r2) => get(r1) ^ get(r2),
+            BoolInstruction::Not(r) => !get(r),
+            BoolInstruction::Nand(r1, r2) => !(get(r1) & get(r2)),
+            BoolInstruction::Nor(r1, r2) => !(get(r1) | get(r2)),
+        }
+    }
+}
+
+impl BoolInstruction {
+    fn operands(&self) -> (RegisterIndex, RegisterIndex) {
+        match *self {
+            BoolInstruction::And(r1, r2)
+            | BoolInstruction::Or(r1, r2)
+            | BoolInstruction::Xor(r1, r2)
+            | BoolInstruction::Nand(r1, r2)
+            | BoolInstruction::Nor(r1, r2) => (r1, r2),
+            BoolInstruction::Not(r) => (r, r),
+        }
+    }
+
+    fn map_operands(&self, f: impl Fn(RegisterIndex) -> RegisterIndex) -> BoolInstruction {
+        match *self {
+            BoolInstruction::And(r1, r2) => BoolInstruction::And(f(r1), f(r2)),
+            BoolInstruction::Or(r1, r2) => BoolInstruction::Or(f(r1), f(r2)),
+            BoolInstruction::Xor(r1, r2) => BoolInstruction::Xor(f(r1), f(r2)),
+            BoolInstruction::Not(r) => BoolInstruction::Not(f(r)),
+            BoolInstruction::Nand(r1, r2) => BoolInstruction::Nand(f(r1), f(r2)),
+            BoolInstruction::Nor(r1, r2) => BoolInstruction::Nor(f(r1), f(r2)),
+        }
+    }
+}
+
+impl fmt::Display for BoolInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BoolInstruction::And(r1, r2) => write!(f, "\nand r{} r{}", r1, r2),
+            BoolInstruction::Or(r1, r2) => write!(f, "\nor r{} r{}", r1, r2),
+            BoolInstruction::Xor(r1, r2) => write!(f, "\nxor r{} r{}", r1, r2),
+            BoolInstruction::Not(r) => write!(f, "\nnot r{}", r),
+            BoolInstruction::Nand(r1, r2) => write!(f, "\nnand r{} r{}", r1, r2),
+            BoolInstruction::Nor(r1, r2) => write!(f, "\nnor r{} r{}", r1, r2),
+        }
+    }
+}
+
+// Every instruction that reads only registers below `register_count`
+fn all_instructions(register_count: usize) -> Vec<BoolInstruction> {
+    let mut instructions = Vec::with_capacity(register_count + register_count * register_count * 5);
+
+    for index in (0..register_count).map(Register::new) {
+        instructions.push(BoolInstruction::Not(index));
+        for index2 in (0..register_count).map(Register::new) {
+            instructions.push(BoolInstruction::And(index, index2));
+            instructions.push(BoolInstruction::Or(index, index2));
+            instructions.push(BoolInstruction::Xor(index, index2));
+            instructions.push(BoolInstruction::Nand(index, index2));
+            instructions.push(BoolInstruction::Nor(index, index2));
+        }
+    }
+
+    instructions
+}
+
+// Register columns as truth tables: bit `i` of a register is its value on
+// test case `i`
+#[derive(Debug, Clone, Copy)]
+pub struct BooleanExecutor {
+    mask: u64,
+}
+
+impl BooleanExecutor {
+    pub fn new(case_count: usize) -> BooleanExecutor {
+        assert!(case_count <= MAX_BOOLEAN_CASES, "at most {} test cases fit in a truth table", MAX_BOOLEAN_CASES);
+        let mask = if case_count == MAX_BOOLEAN_CASES { !0 } else { (1 << case_count) - 1 };
+        BooleanExecutor { mask }
+    }
+
+    pub fn step(&self, registers: &mut Vec<u64>, inst: &BoolInstruction) {
+        let column = inst.eval(registers) & self.mask;
+        registers.push(column);
+    }
+
+    pub fn execute(&self, inputs: &[u64], instructions: &[BoolInstruction]) -> Vec<u64> {
+        let mut registers = inputs.to_vec();
+        for inst in instructions {
+            self.step(&mut registers, inst);
+        }
+        registers
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BooleanVerifier {
+    target: u64,
+}
+
+impl BooleanVerifier {
+    pub fn new(target: u64) -> BooleanVerifier {
+        BooleanVerifier { target }
+    }
+
+    // Comparing a whole truth table is a single integer comparison
+    pub fn verify(&self, registers: &[u64]) -> Option<RegisterIndex> {
+        registers.iter().position(|&column| column == self.target).map(Register::new)
+    }
+}
+
+// A program together with the registers it leaves behind
+#[derive(Debug, Clone)]
+struct BoolExecution {
+    instructions: Vec<BoolInstruction>,
+    registers: Vec<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BooleanSynthesizer {
+    executor: BooleanExecutor,
+    verifier: BooleanVerifier,
+    inputs: Vec<u64>,
+    max_depth: Option<usize>,
+}
+
+impl BooleanSynthesizer {
+    pub fn new(inputs: &[Vec<bool>], outputs: &[bool]) -> Result<BooleanSynthesizer, SynthesisError> {
+        let arity = match inputs.first() {
+            Some(row) => row.len(),
+            None => return Err(SynthesisError::EmptySpec),
+        };
+        if arity == 0 {
+            return Err(SynthesisError::NoInputRegisters);
+        }
+        if let Some(case) = inputs.iter().position(|row| row.len() != arity) {
+            return Err(SynthesisError::InconsistentArity {
+                case,
+                expected: arity,
+                found: inputs[case].len(),
+            });
+        }
+        if outputs.len() != inputs.len() {
+            return Err(SynthesisError::OutputCount {
+                expected: inputs.len(),
+                found: outputs.len(),
+            });
+        }
+        if inputs.len() > MAX_BOOLEAN_CASES {
+            return Err(SynthesisError::TooManyCases {
+                limit: MAX_BOOLEAN_CASES,
+                found: inputs.len(),
+            });
+        }
+
+        let pack = |bits: &mut dyn Iterator<Item = bool>| {
+            bits.enumerate().fold(0u64, |column, (case, bit)| column | ((bit as u64) << case))
+        };
+        let columns = (0..arity)
+            .map(|reg| pack(&mut inputs.iter().map(|row| row[reg])))
+            .collect();
+
+        Ok(BooleanSynthesizer {
+            executor: BooleanExecutor::new(inputs.len()),
+            verifier: BooleanVerifier::new(pack(&mut outputs.iter().cloned())),
+            inputs: columns,
+            max_depth: None,
+        })
+    }
+
+    // Stop breadth-first expansion after `max_depth` instructions
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    // Breadth-first search, deduplicating executions by the set of columns
+    // they hold: two programs computing the same columns in a different
+    // order can reach the same things. Instructions that recompute a column
+    // the execution already has are skipped, since a copy is never needed.
+    pub fn run(&self) -> Option<(Vec<BoolInstruction>, RegisterIndex)> {
+        let start = BoolExecution {
+            instructions: vec![],
+            registers: self.inputs.clone(),
+        };
+        if let Some(reg) = self.verifier.verify(&start.registers) {
+            return Some((start.instructions, reg));
+        }
+
+        let mut frontier = vec![start];
+        let mut depth = 0;
+
+        while !frontier.is_empty() && self.max_depth.is_none_or(|max| depth < max) {
+            depth += 1;
+
+            let candidates = frontier
+                .par_iter()
+                .flat_map(|exe| {
+                    all_instructions(exe.registers.len())
+                        .into_iter()
+                        .filter_map(|inst| {
+                            let mut registers = exe.registers.clone();
+                            self.executor.step(&mut registers, &inst);
+                            if exe.registers.contains(registers.last().unwrap()) {
+                                return None;
+                            }
+
+                            let mut instructions = exe.instructions.clone();
+                            instructions.push(inst);
+                            Some(BoolExecution { instructions, registers })
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut seen = HashSet::with_capacity(candidates.len());
+            frontier = candidates
+                .into_iter()
+                .filter(|exe| {
+                    let mut columns = exe.registers.clone();
+                    columns.sort_unstable();
+                    seen.insert(columns)
+                })
+                .collect();
+
+            if let Some(found) = frontier
+                .par_iter()
+                .map(|exe| self.verifier.verify(&exe.registers).map(|reg| (exe.instructions.clone(), reg)))
+                .find_any(|result| result.is_some())
+            {
+                return found;
+            }
+        }
+
+        None
+    }
+}
+
+// Truth table of input `input` over all 2^input_count assignments, where
+// row `r` assigns bit `k` of `r` to input `k`
+fn input_table(input: usize, input_count: usize) -> Vec<u64> {
+    let rows = 1usize << input_count;
+    let mut table = vec![0u64; rows.div_ceil(64)];
+    for row in (0..rows).filter(|row| row >> input & 1 == 1) {
+        table[row / 64] |= 1 << (row % 64);
+    }
+    table
+}
+
+// Rewrite a program over `n_inputs` inputs so that it holds at most one
+// register per logical function. Each register is keyed by its truth table
+// over every input assignment, not just a spec's test cases; an
+// instruction that recomputes a function already held (`not` of a `not`,
+// `and r0 r0`, ...) is dropped and later reads of it go to the earlier
+// register instead. The input count is not inferred, since a program need
+// not read its highest input. BooleanSynthesizer::run does not use this:
+// its frontier already merges programs that agree on the test cases, which
+// merges at least as much.
+pub fn bool_canonicalize(instructions: &[BoolInstruction], n_inputs: usize) -> Vec<BoolInstruction> {
+    assert!(n_inputs > 0, "a program needs at least one input");
+    assert!(n_inputs <= 20, "truth tables over {} inputs are too large to build", n_inputs);
+    for (i, inst) in instructions.iter().enumerate() {
+        let (r1, r2) = inst.operands();
+        assert!(
+            r1.as_index().max(r2.as_index()) < n_inputs + i,
+            "instruction {} reads a register that does not exist yet",
+            i
+        );
+    }
+
+    let rows = 1usize << n_inputs;
+    let mask = if rows >= 64 { !0 } else { (1u64 << rows) - 1 };
+
+    let mut tables = (0..n_inputs).map(|input| input_table(input, n_inputs)).collect::<Vec<_>>();
+    let mut known = tables
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(reg, table)| (table, Register::new(reg)))
+        .collect::<HashMap<_, _>>();
+    // Where each register of the original program now lives
+    let mut renamed = (0..n_inputs).map(Register::new).collect::<Vec<_>>();
+    let mut canonical = Vec::new();
+
+    for inst in instructions {
+        let inst = inst.map_operands(|r| renamed[r.as_index()]);
+        let mut table = (0..tables[0].len())
+            .map(|word| {
+                let words = tables.iter().map(|table| table[word]).collect::<Vec<_>>();
+                inst.eval(&words)
+            })
+            .collect::<Vec<_>>();
+        *table.last_mut().unwrap() &= mask;
+
+        match known.get(&table) {
+            Some(&reg) => renamed.push(reg),
+            None => {
+                let reg = Register::new(tables.len());
+                known.insert(table.clone(), reg);
+                tables.push(table);
+                renamed.push(reg);
+                canonical.push(inst);
+            }
+        }
+    }
+
+    canonical
+}
@@ -0,0 +1,77 @@
+use sha2::{Digest, Sha256};
+
+use std::path::PathBuf;
+
+use encoding::{InstructionEncoder, VarIntEncoder};
+use testsuite::TestSuite;
+use {OwnedExecution, Register, RegisterIndex};
+
+#[derive(Debug, Clone)]
+pub struct CachedSolution {
+    pub execution: OwnedExecution,
+    pub register: RegisterIndex,
+}
+
+// Solutions on disk, keyed by the SHA-256 of a spec's test cases in sorted
+// order, so the same cases in any order share an entry. Domains are not
+// part of the key. A value is the output register in eight little-endian
+// bytes followed by the program in VarIntEncoder's format.
+pub struct SynthesisCache {
+    db: sled::Db,
+}
+
+fn spec_key(spec: &TestSuite) -> Vec<u8> {
+    let mut cases = spec.cases().iter().map(|case| (&case.inputs, case.output)).collect::<Vec<_>>();
+    cases.sort();
+
+    let mut hasher = Sha256::new();
+    for (inputs, output) in cases {
+        hasher.update((inputs.len() as u64).to_le_bytes());
+        for &value in inputs {
+            hasher.update((value as i64).to_le_bytes());
+        }
+        hasher.update((output as i64).to_le_bytes());
+    }
+    hasher.finalize().to_vec()
+}
+
+impl SynthesisCache {
+    pub fn new(path: PathBuf) -> sled::Result<SynthesisCache> {
+        Ok(SynthesisCache { db: sled::open(path)? })
+    }
+
+    // None when nothing is stored, or when the stored program cannot be read
+    // back or does not solve the spec
+    pub fn lookup(&self, spec: &TestSuite) -> Option<CachedSolution> {
+        let value = self.db.get(spec_key(spec)).ok()??;
+        if value.len() < 8 {
+            return None;
+        }
+        let (register, program) = value.split_at(8);
+        let mut register_bytes = [0; 8];
+        register_bytes.copy_from_slice(register);
+
+        let instructions = VarIntEncoder.decode_program(program).ok()?;
+        let execution = OwnedExecution::try_replay(spec.inputs(), instructions)?;
+        let register = Register::new(u64::from_le_bytes(register_bytes) as usize);
+        if register.as_index() >= execution.output[0].len() {
+            return None;
+        }
+        let outputs = spec.outputs();
+        if !execution.output.iter().zip(&outputs).all(|(row, &output)| row[register.as_index()] == output) {
+            return None;
+        }
+        Some(CachedSolution { execution, register })
+    }
+
+    pub fn store(&self, spec: &TestSuite, sol: &OwnedExecution, reg: RegisterIndex) -> sled::Result<()> {
+        let mut value = (reg.as_index() as u64).to_le_bytes().to_vec();
+        let program = VarIntEncoder
+            .encode_program(&sol.instructions)
+            .map_err(|e| sled::Error::Unsupported(e.to_string()))?;
+        value.extend(program);
+        self.db.insert(spec_key(spec), value)?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use serde::de::Error as _;
+use serde::Deserialize;
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use synthesizer::{Strategy, SynthesizerBuilder};
+use InstructionVariant;
+
+// Builder options read from a TOML file, such as
+//
+//     max_depth = 6
+//     strategy = "breadth_first"
+//     enabled_instructions = ["Add", "Mul", "Neg"]
+//     timeout_secs = 60
+//
+// Every key is optional and unknown keys are errors, as is a beam_width
+// of 0.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SynthesizerConfig {
+    pub max_depth: Option<usize>,
+    pub max_registers: Option<usize>,
+    pub strategy: Option<Strategy>,
+    pub beam_width: Option<usize>,
+    pub threads: Option<usize>,
+    pub enabled_instructions: Option<Vec<InstructionVariant>>,
+    pub dedup_lookback: Option<usize>,
+    pub timeout_secs: Option<u64>,
+}
+
+impl SynthesizerConfig {
+    // A file that cannot be read is reported as a TOML error too
+    pub fn from_toml(path: &Path) -> Result<SynthesizerConfig, toml::de::Error> {
+        let text = fs::read_to_string(path).map_err(toml::de::Error::custom)?;
+        let config: SynthesizerConfig = toml::from_str(&text)?;
+        if config.beam_width == Some(0) {
+            return Err(toml::de::Error::custom("beam_width must be at least 1"));
+        }
+        Ok(config)
+    }
+
+    // A beam_width of 0, which from_toml rejects, is ignored rather than
+    // passed on to panic in with_beam_width
+    pub fn into_builder(self) -> SynthesizerBuilder {
+        let mut builder = SynthesizerBuilder::new();
+        if let Some(max_depth) = self.max_depth {
+            builder = builder.with_max_depth(max_depth);
+        }
+        if let Some(max_registers) = self.max_registers {
+            builder = builder.with_max_registers(max_registers);
+        }
+        if let Some(strategy) = self.strategy {
+            builder = builder.strategy(strategy);
+        }
+        if let Some(beam_width) = self.beam_width.filter(|&width| width > 0) {
+            builder = builder.with_beam_width(beam_width);
+        }
+        if let Some(threads) = self.threads {
+            builder = builder.with_threads(threads);
+        }
+        if let Some(variants) = self.enabled_instructions {
+            builder = builder.with_enabled_instructions(variants);
+        }
+        if let Some(generations) = self.dedup_lookback {
+            builder = builder.with_dedup_lookback(generations);
+        }
+        if let Some(secs) = self.timeout_secs {
+            builder = builder.with_timeout(Duration::from_secs(secs));
+        }
+        builder
+    }
+}
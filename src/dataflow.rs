@@ -0,0 +1,50 @@
+use {compute_ordering, Instruction, RegisterIndex};
+
+// The registers `inst` reads before writing them itself; for a fused
+// instruction, those its parts read from outside it
+fn reads(inst: &Instruction) -> Vec<RegisterIndex> {
+    match *inst {
+        Instruction::Mov(r1, _) => vec![r1],
+        Instruction::Add(r1, r2) | Instruction::Mul(r1, r2) => vec![r1, r2],
+        Instruction::Neg(r) => vec![r],
+        Instruction::Fused(ref fused) => {
+            let mut outside = Vec::new();
+            for (index, part) in fused.0.iter().enumerate() {
+                let written = compute_ordering(&fused.0[..index]);
+                outside.extend(reads(part).into_iter().filter(|reg| !written.contains(reg)));
+            }
+            outside
+        }
+    }
+}
+
+// `result[i]` lists, in increasing order, the earlier instructions whose
+// results instruction `i` reads: for each register it reads, the last
+// instruction before it that wrote that register. Reads of untouched input
+// registers depend on nothing. Every edge points backwards, so the graph is
+// a DAG in program order. A fused instruction is one node that writes
+// every register its parts do.
+pub fn register_dependency_graph(instructions: &[Instruction]) -> Vec<Vec<usize>> {
+    let mut last_writer: Vec<Option<usize>> = Vec::new();
+
+    instructions
+        .iter()
+        .enumerate()
+        .map(|(index, inst)| {
+            let mut dependencies = reads(inst)
+                .iter()
+                .filter_map(|reg| last_writer.get(reg.as_index()).and_then(|&writer| writer))
+                .collect::<Vec<_>>();
+            dependencies.sort();
+            dependencies.dedup();
+
+            for written in compute_ordering(std::slice::from_ref(inst)) {
+                if last_writer.len() <= written.as_index() {
+                    last_writer.resize(written.as_index() + 1, None);
+                }
+                last_writer[written.as_index()] = Some(index);
+            }
+            dependencies
+        })
+        .collect()
+}
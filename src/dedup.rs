@@ -0,0 +1,96 @@
+use indexmap::IndexSet;
+
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use {Execution, Value};
+
+// Pairwise checking is quadratic, so the dedup-check feature only checks
+// frontiers up to this size
+pub const DEDUP_CHECK_LIMIT: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DedupError {
+    // Eq and the outputs disagree about a pair
+    Inconsistency {
+        a_hash: u64,
+        b_hash: u64,
+        a_output: Vec<Vec<Value>>,
+        b_output: Vec<Vec<Value>>,
+    },
+    // Equal outputs hashed differently, so the set never compared them
+    HashMismatch {
+        a_hash: u64,
+        b_hash: u64,
+        output: Vec<Vec<Value>>,
+    },
+    // Two entries with the same output both survived dedup
+    Duplicate { hash: u64, output: Vec<Vec<Value>> },
+}
+
+impl fmt::Display for DedupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DedupError::Inconsistency { a_hash, b_hash, .. } => write!(
+                f,
+                "frontier entries with hashes {:x} and {:x} disagree between Eq and their outputs",
+                a_hash, b_hash
+            ),
+            DedupError::HashMismatch { a_hash, b_hash, .. } => write!(
+                f,
+                "frontier entries with equal outputs have different hashes {:x} and {:x}",
+                a_hash, b_hash
+            ),
+            DedupError::Duplicate { hash, .. } => {
+                write!(f, "two frontier entries share the output with hash {:x}", hash)
+            }
+        }
+    }
+}
+
+impl Error for DedupError {}
+
+fn hash_of(exe: &Execution) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    exe.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Equal outputs must mean equal hashes and equal entries, and unequal
+// outputs unequal entries; a deduplicated frontier should then hold no
+// pair with equal outputs at all. Anything else means Hash, Eq and the
+// outputs have drifted apart.
+pub fn verify_dedup_soundness(frontier: &IndexSet<Execution>) -> Result<(), DedupError> {
+    let entries = frontier.iter().map(|exe| (exe, hash_of(exe))).collect::<Vec<_>>();
+
+    for (i, &(a, a_hash)) in entries.iter().enumerate() {
+        for &(b, b_hash) in &entries[i + 1..] {
+            let same_output = a.output == b.output;
+            if same_output && a_hash != b_hash {
+                return Err(DedupError::HashMismatch {
+                    a_hash,
+                    b_hash,
+                    output: a.output.clone(),
+                });
+            }
+            if same_output != (a == b) {
+                return Err(DedupError::Inconsistency {
+                    a_hash,
+                    b_hash,
+                    a_output: a.output.clone(),
+                    b_output: b.output.clone(),
+                });
+            }
+            if same_output {
+                return Err(DedupError::Duplicate {
+                    hash: a_hash,
+                    output: a.output.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use {Instruction, OwnedExecution, RegisterIndex};
+
+// Levenshtein distance counting whole instructions as symbols
+fn edit_distance(a: &[Instruction], b: &[Instruction]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, inst_a) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, inst_b) in b.iter().enumerate() {
+            let substitution = previous[j] + if inst_a == inst_b { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+pub fn pairwise_edit_distance(solutions: &[(OwnedExecution, RegisterIndex)]) -> Vec<Vec<usize>> {
+    solutions
+        .iter()
+        .map(|(a, _)| {
+            solutions
+                .iter()
+                .map(|(b, _)| edit_distance(&a.instructions, &b.instructions))
+                .collect()
+        })
+        .collect()
+}
+
+// Greedy max-min selection: start from the solution farthest from all the
+// others in total, then keep adding whichever is farthest from its nearest
+// already-chosen neighbour
+pub fn most_diverse_subset(solutions: &[(OwnedExecution, RegisterIndex)], k: usize) -> Vec<usize> {
+    let distances = pairwise_edit_distance(solutions);
+    let k = k.min(solutions.len());
+
+    let first = (0..solutions.len()).max_by_key(|&i| (distances[i].iter().sum::<usize>(), usize::MAX - i));
+    let mut chosen = first.into_iter().collect::<Vec<_>>();
+
+    while chosen.len() < k {
+        let next = (0..solutions.len())
+            .filter(|i| !chosen.contains(i))
+            .max_by_key(|&i| {
+                let nearest = chosen.iter().map(|&c| distances[i][c]).min().unwrap();
+                (nearest, usize::MAX - i)
+            })
+            .unwrap();
+        chosen.push(next);
+    }
+
+    chosen.truncate(k);
+    chosen
+}
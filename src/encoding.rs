@@ -0,0 +1,262 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+
+use {FusedInstruction, Instruction, Register};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    UnknownVariant(u8),
+    // The bytes ended partway through an instruction
+    Truncated,
+    // Bytes left over after a complete instruction
+    TrailingBytes,
+    // A variable-length register index too large for usize
+    Overflow,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::UnknownVariant(byte) => write!(f, "unknown instruction variant {}", byte),
+            DecodeError::Truncated => write!(f, "instruction bytes end early"),
+            DecodeError::TrailingBytes => write!(f, "extra bytes after the instruction"),
+            DecodeError::Overflow => write!(f, "register index overflows"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    // A register index too large for the format
+    RegisterTooLarge(Register),
+    // The format has no room for fused instructions
+    Fused,
+    // An instruction whose encoding is too long for its one-byte length
+    TooLong(usize),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::RegisterTooLarge(register) => write!(f, "register r{} does not fit in a byte", register),
+            EncodeError::Fused => write!(f, "a fused instruction does not fit in three bytes"),
+            EncodeError::TooLong(len) => write!(f, "{} bytes is too long for one instruction", len),
+        }
+    }
+}
+
+impl Error for EncodeError {}
+
+fn register_byte(register: Register) -> Result<u8, EncodeError> {
+    if register.as_index() > u8::MAX as usize {
+        return Err(EncodeError::RegisterTooLarge(register));
+    }
+    Ok(register.as_index() as u8)
+}
+
+impl Instruction {
+    pub fn to_discriminant(&self) -> u8 {
+        match *self {
+            Instruction::Mov(..) => 0,
+            Instruction::Add(..) => 1,
+            Instruction::Mul(..) => 2,
+            Instruction::Neg(..) => 3,
+            Instruction::Fused(..) => 4,
+        }
+    }
+
+    // [variant, src, dst]; Neg leaves dst zero. Registers past r255 and
+    // fused instructions need VarIntEncoder.
+    pub fn encode(&self) -> Result<[u8; 3], EncodeError> {
+        match *self {
+            Instruction::Mov(r1, r2) | Instruction::Add(r1, r2) | Instruction::Mul(r1, r2) => {
+                Ok([self.to_discriminant(), register_byte(r1)?, register_byte(r2)?])
+            }
+            Instruction::Neg(r) => Ok([self.to_discriminant(), register_byte(r)?, 0]),
+            Instruction::Fused(..) => Err(EncodeError::Fused),
+        }
+    }
+
+    pub fn decode(bytes: [u8; 3]) -> Result<Instruction, DecodeError> {
+        let (r1, r2) = (Register::new(bytes[1] as usize), Register::new(bytes[2] as usize));
+        match bytes[0] {
+            0 => Ok(Instruction::Mov(r1, r2)),
+            1 => Ok(Instruction::Add(r1, r2)),
+            2 => Ok(Instruction::Mul(r1, r2)),
+            3 => Ok(Instruction::Neg(r1)),
+            byte => Err(DecodeError::UnknownVariant(byte)),
+        }
+    }
+}
+
+// A byte format for single instructions. Programs are written as each
+// instruction's length in one byte, followed by its encoding.
+pub trait InstructionEncoder: Debug + Send + Sync {
+    fn encode(&self, inst: &Instruction) -> Result<Vec<u8>, EncodeError>;
+
+    // `bytes` must hold exactly one instruction
+    fn decode(&self, bytes: &[u8]) -> Result<Instruction, DecodeError>;
+
+    fn encode_program(&self, instructions: &[Instruction]) -> Result<Vec<u8>, EncodeError> {
+        let mut bytes = Vec::new();
+        for inst in instructions {
+            let encoded = self.encode(inst)?;
+            if encoded.len() > u8::MAX as usize {
+                return Err(EncodeError::TooLong(encoded.len()));
+            }
+            bytes.push(encoded.len() as u8);
+            bytes.extend(encoded);
+        }
+        Ok(bytes)
+    }
+
+    fn decode_program(&self, mut bytes: &[u8]) -> Result<Vec<Instruction>, DecodeError> {
+        let mut instructions = Vec::new();
+        while let Some((&len, rest)) = bytes.split_first() {
+            if rest.len() < len as usize {
+                return Err(DecodeError::Truncated);
+            }
+            let (encoded, rest) = rest.split_at(len as usize);
+            instructions.push(self.decode(encoded)?);
+            bytes = rest;
+        }
+        Ok(instructions)
+    }
+}
+
+// The three-byte [variant, src, dst] form of Instruction::encode
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedWidthEncoder;
+
+impl InstructionEncoder for FixedWidthEncoder {
+    fn encode(&self, inst: &Instruction) -> Result<Vec<u8>, EncodeError> {
+        Ok(inst.encode()?.to_vec())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Instruction, DecodeError> {
+        match bytes.len() {
+            3 => Instruction::decode([bytes[0], bytes[1], bytes[2]]),
+            len if len < 3 => Err(DecodeError::Truncated),
+            _ => Err(DecodeError::TrailingBytes),
+        }
+    }
+}
+
+// The variant byte followed by each operand in unsigned LEB128, so any
+// register index fits and small ones take a single byte. Neg has one
+// operand. A fused instruction has its part count in LEB128 and then
+// each part as a LEB128 length and that part's encoding.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VarIntEncoder;
+
+fn write_leb128(mut value: usize, bytes: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn read_leb128(bytes: &mut &[u8]) -> Result<usize, DecodeError> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes.split_first().ok_or(DecodeError::Truncated)?;
+        *bytes = rest;
+        let low = (byte & 0x7f) as usize;
+        if shift >= usize::BITS || (low << shift) >> shift != low {
+            return Err(DecodeError::Overflow);
+        }
+        value |= low << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+impl InstructionEncoder for VarIntEncoder {
+    fn encode(&self, inst: &Instruction) -> Result<Vec<u8>, EncodeError> {
+        let mut bytes = vec![inst.to_discriminant()];
+        match *inst {
+            Instruction::Mov(r1, r2) | Instruction::Add(r1, r2) | Instruction::Mul(r1, r2) => {
+                write_leb128(r1.as_index(), &mut bytes);
+                write_leb128(r2.as_index(), &mut bytes);
+            }
+            Instruction::Neg(r) => write_leb128(r.as_index(), &mut bytes),
+            Instruction::Fused(ref fused) => {
+                write_leb128(fused.0.len(), &mut bytes);
+                for part in &fused.0 {
+                    let encoded = self.encode(part)?;
+                    write_leb128(encoded.len(), &mut bytes);
+                    bytes.extend(encoded);
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Instruction, DecodeError> {
+        let (&variant, mut rest) = bytes.split_first().ok_or(DecodeError::Truncated)?;
+        let inst = match variant {
+            0..=2 => {
+                let r1 = Register::new(read_leb128(&mut rest)?);
+                let r2 = Register::new(read_leb128(&mut rest)?);
+                match variant {
+                    0 => Instruction::Mov(r1, r2),
+                    1 => Instruction::Add(r1, r2),
+                    _ => Instruction::Mul(r1, r2),
+                }
+            }
+            3 => Instruction::Neg(Register::new(read_leb128(&mut rest)?)),
+            4 => {
+                let count = read_leb128(&mut rest)?;
+                let mut parts = Vec::new();
+                for _ in 0..count {
+                    let len = read_leb128(&mut rest)?;
+                    if rest.len() < len {
+                        return Err(DecodeError::Truncated);
+                    }
+                    let (part, tail) = rest.split_at(len);
+                    parts.push(self.decode(part)?);
+                    rest = tail;
+                }
+                Instruction::Fused(FusedInstruction(parts))
+            }
+            byte => return Err(DecodeError::UnknownVariant(byte)),
+        };
+
+        if rest.is_empty() {
+            Ok(inst)
+        } else {
+            Err(DecodeError::TrailingBytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncodeError, FixedWidthEncoder, InstructionEncoder, VarIntEncoder};
+    use {FusedInstruction, Instruction, Register};
+
+    #[test]
+    fn fixed_width_rejects_what_does_not_fit() {
+        let wide = Instruction::Add(Register::new(0), Register::new(256));
+        assert_eq!(wide.encode(), Err(EncodeError::RegisterTooLarge(Register::new(256))));
+
+        let fused = Instruction::Fused(FusedInstruction(vec![Instruction::Neg(Register::new(0))]));
+        assert_eq!(fused.encode(), Err(EncodeError::Fused));
+        let program = vec![wide, fused];
+        assert_eq!(FixedWidthEncoder.encode_program(&program), Err(EncodeError::RegisterTooLarge(Register::new(256))));
+
+        let bytes = VarIntEncoder.encode_program(&program).unwrap();
+        assert_eq!(VarIntEncoder.decode_program(&bytes), Ok(program));
+    }
+}
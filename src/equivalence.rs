@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use {Execution, Value};
+
+// A register file per test case; executions with equal matrices behave the
+// same on the spec
+pub type OutputMatrix = Vec<Vec<Value>>;
+
+// All the programs sharing one output matrix, shortest first
+pub type EquivalenceClass<'a> = Vec<Execution<'a>>;
+
+// Group programs by behavior instead of discarding all but one as the
+// frontier's IndexSet does
+pub fn group_by_output<'a>(
+    programs: impl IntoIterator<Item = Execution<'a>>,
+) -> HashMap<OutputMatrix, EquivalenceClass<'a>> {
+    let mut classes: HashMap<OutputMatrix, EquivalenceClass<'a>> = HashMap::new();
+    for exe in programs {
+        classes.entry(exe.output.clone()).or_default().push(exe);
+    }
+
+    for class in classes.values_mut() {
+        class.sort_by_key(Execution::depth);
+    }
+    classes
+}
+
+// The shortest program of each class
+pub fn representatives<'a>(classes: HashMap<OutputMatrix, EquivalenceClass<'a>>) -> Vec<Execution<'a>> {
+    classes
+        .into_values()
+        .filter_map(|class| class.into_iter().next())
+        .collect()
+}
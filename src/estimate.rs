@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use synthesizer::{Synthesizer, SynthesizerBuilder};
+use testsuite::TestSuite;
+use score;
+
+// Generations needed before there is anything to fit
+const MIN_GENERATIONS: usize = 3;
+
+// What benchmark_spec makes of a spec from its first few generations
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardnessEstimate {
+    // Program length the search is likely to need, or None if the score is
+    // not improving enough to say
+    pub estimated_depth: Option<usize>,
+    // From 0.0 to 1.0; 1.0 only when the answer was seen, not extrapolated
+    pub confidence: f64,
+    pub reasoning: String,
+}
+
+// What the unseeded breadth-first search saw in one generation
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GenerationStats {
+    pub(crate) generation: usize,
+    pub(crate) elapsed: Duration,
+    pub(crate) frontier: usize,
+    // Sum of arena::execution_bytes over the frontier
+    pub(crate) bytes: usize,
+    // Best `score` in the frontier; 0.0 means solved
+    pub(crate) best_score: f64,
+}
+
+// Least-squares slope and intercept of y against x
+fn fit_line(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let covariance = points.iter().map(|&(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+    let variance = points.iter().map(|&(x, _)| (x - mean_x) * (x - mean_x)).sum::<f64>();
+    if variance == 0.0 {
+        return None;
+    }
+    let slope = covariance / variance;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+impl Synthesizer {
+    // How much longer the last run would have needed, extrapolated from its
+    // generations: generation times are fitted as exponential in depth and
+    // the best score as linear, and the remaining generations are timed up
+    // to the one where the score line reaches zero. None with fewer than
+    // three generations recorded, when the score is not improving, or when
+    // the predicted solution lies past `depth_limit`.
+    pub fn estimated_remaining(&self, depth_limit: usize) -> Option<Duration> {
+        let stats = self.generation_stats.lock().unwrap();
+        if stats.len() < MIN_GENERATIONS {
+            return None;
+        }
+
+        let times = stats
+            .iter()
+            .map(|s| (s.generation as f64, s.elapsed.as_secs_f64().max(1e-9).ln()))
+            .collect::<Vec<_>>();
+        let scores = stats.iter().map(|s| (s.generation as f64, s.best_score)).collect::<Vec<_>>();
+        let (time_growth, time_base) = fit_line(&times)?;
+        let (score_slope, score_base) = fit_line(&scores)?;
+        if score_slope <= 0.0 {
+            return None;
+        }
+
+        let last = stats.last().unwrap().generation;
+        let solved_at = ((-score_base / score_slope).ceil() as usize).max(last + 1);
+        if solved_at > depth_limit {
+            return None;
+        }
+
+        let seconds = (last + 1..=solved_at)
+            .map(|generation| (time_base + time_growth * generation as f64).exp())
+            .sum::<f64>();
+        if seconds.is_finite() {
+            Some(Duration::from_secs_f64(seconds))
+        } else {
+            None
+        }
+    }
+
+    // Runs breadth-first search on `spec` for three generations and
+    // extrapolates from them: how fast the frontier grows, what share of
+    // each generation scores better than the best before it, and where a
+    // line through the best scores reaches zero. Confidence is the share of
+    // generations that improved the best score, scaled to stay below 1.0
+    // for anything not actually seen. Builder limits and monitors do not
+    // apply, as with run_n_generations.
+    pub fn benchmark_spec(spec: &TestSuite, builder: &SynthesizerBuilder) -> HardnessEstimate {
+        let mut synthesizer = builder.build(spec);
+        let start = synthesizer.snapshot();
+        let mut sizes = vec![start.frontier().len()];
+        let mut best_scores = vec![score(&start.frontier()[0].output, &synthesizer.outputs)];
+        let mut improving = Vec::new();
+
+        for _ in 0..MIN_GENERATIONS {
+            let result = synthesizer.run_n_generations(1);
+            if !result.solutions.is_empty() {
+                return HardnessEstimate {
+                    estimated_depth: Some(result.generation),
+                    confidence: 1.0,
+                    reasoning: format!("solved at generation {}", result.generation),
+                };
+            }
+            if result.frontier_size == 0 {
+                return HardnessEstimate {
+                    estimated_depth: None,
+                    confidence: 1.0,
+                    reasoning: format!("nothing left to extend after generation {}", result.generation),
+                };
+            }
+
+            let scores = synthesizer
+                .current_frontier()
+                .iter()
+                .map(|exe| score(&exe.output, &synthesizer.outputs))
+                .collect::<Vec<_>>();
+            let previous = *best_scores.last().unwrap();
+            let better = scores.iter().filter(|&&value| value > previous).count();
+            improving.push(better as f64 / scores.len() as f64);
+            best_scores.push(scores.into_iter().fold(f64::NEG_INFINITY, f64::max));
+            sizes.push(result.frontier_size);
+        }
+
+        let generations = improving.len();
+        let growth = (*sizes.last().unwrap() as f64 / sizes[0] as f64).powf(1.0 / generations as f64);
+        let improving_share = improving.iter().sum::<f64>() / generations as f64;
+        let improved = best_scores.windows(2).filter(|pair| pair[1] > pair[0]).count();
+        let best = *best_scores.last().unwrap();
+        let mut reasoning = format!(
+            "frontier grew {:.1}x per generation to {} programs; {:.1}% of programs beat the best score before \
+             them; best score {} after {} generations",
+            growth,
+            sizes.last().unwrap(),
+            improving_share * 100.0,
+            best,
+            generations
+        );
+
+        let points = best_scores
+            .iter()
+            .enumerate()
+            .map(|(generation, &best)| (generation as f64, best))
+            .collect::<Vec<_>>();
+        let estimated_depth = match fit_line(&points) {
+            Some((slope, intercept)) if slope > 0.0 => {
+                let depth = ((-intercept / slope).ceil() as usize).max(generations + 1);
+                reasoning.push_str(&format!(", improving by {:.1} a generation to reach 0 at depth {}", slope, depth));
+                Some(depth)
+            }
+            _ => {
+                reasoning.push_str(", not improving");
+                None
+            }
+        };
+
+        HardnessEstimate {
+            estimated_depth,
+            confidence: if estimated_depth.is_some() { improved as f64 / (generations + 1) as f64 } else { 0.0 },
+            reasoning,
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use rand::Rng;
+
+use std::error::Error;
+use std::fmt;
+
+use testsuite::{Domain, TestSuite, UNBOUNDED};
+use {Execution, Instruction, RegisterIndex, Value};
+
+// An input the program gets wrong. `actual` is read from the register that
+// had matched on every input before this one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterExample {
+    pub inputs: Vec<Value>,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+impl fmt::Display for CounterExample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "on inputs {:?} expected {} but got {}", self.inputs, self.expected, self.actual)
+    }
+}
+
+impl Error for CounterExample {}
+
+// Registers after running `instructions` on one set of inputs
+pub(crate) fn run_on(instructions: &[Instruction], inputs: &[Value]) -> Vec<Value> {
+    let mut registers = inputs.to_vec();
+    for inst in instructions {
+        inst.apply(&mut registers);
+    }
+    registers
+}
+
+// What the program behind `exe` leaves in `output_register` for `inputs`,
+// which need not be one of its test cases
+pub fn verify_counterexample(exe: &Execution, output_register: RegisterIndex, inputs: &[Value]) -> Value {
+    assert_eq!(inputs.len(), exe.input_count(), "the program was synthesized for a different number of inputs");
+    run_on(&exe.linearize(), inputs)[output_register.as_index()]
+}
+
+// Where differential_test draws inputs from: wide enough to leave the
+// usual test cases behind, narrow enough that a reference doing plain
+// arithmetic on a few inputs will not overflow
+pub const DIFF_TEST_RANGE: Domain = (-1000, 1000);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffTestResult {
+    pub cases: usize,
+    // Every input where the program and the reference disagree, in the
+    // order they were tried
+    pub discrepancies: Vec<CounterExample>,
+}
+
+impl DiffTestResult {
+    pub fn passed(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+// Run the program behind `exe` and the reference `f` side by side on
+// `n_cases` random inputs from DIFF_TEST_RANGE, to catch a program that
+// fits its test cases by accident. Unlike exhaustively_verify, the result
+// must be in `output_register`, and every disagreement is reported.
+pub fn differential_test(
+    exe: &Execution,
+    output_register: RegisterIndex,
+    f: &impl Fn(&[Value]) -> Value,
+    n_cases: usize,
+    rng: &mut impl Rng,
+) -> DiffTestResult {
+    let instructions = exe.linearize();
+    let (lo, hi) = DIFF_TEST_RANGE;
+    let mut result = DiffTestResult {
+        cases: n_cases,
+        discrepancies: vec![],
+    };
+
+    for _ in 0..n_cases {
+        let inputs = (0..exe.input_count()).map(|_| rng.gen_range(lo..=hi)).collect::<Vec<_>>();
+        let expected = f(&inputs);
+        let actual = run_on(&instructions, &inputs)[output_register.as_index()];
+        if actual != expected {
+            result.discrepancies.push(CounterExample {
+                inputs,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    result
+}
+
+// Every assignment of values to inputs, input `i` drawn from `values[i]`,
+// in odometer order
+pub(crate) fn assignments(values: Vec<Vec<Value>>) -> impl Iterator<Item = Vec<Value>> {
+    let total = values.iter().try_fold(1usize, |count, choices| count.checked_mul(choices.len()));
+    let total = total.expect("too many input combinations to enumerate");
+
+    (0..total).map(move |mut index| {
+        values
+            .iter()
+            .map(|choices| {
+                let value = choices[index % choices.len()];
+                index /= choices.len();
+                value
+            })
+            .collect()
+    })
+}
+
+// Check the program behind `exe` against `f` on every combination of domain
+// values rather than just its test cases. As with verify, any register may
+// hold the result, but it must be the same register for every input.
+pub fn exhaustively_verify(
+    exe: &Execution,
+    domain: &[Value],
+    n_inputs: usize,
+    f: impl Fn(&[Value]) -> Value,
+) -> Result<(), CounterExample> {
+    assert_eq!(exe.inputs()[0].len(), n_inputs, "the program was synthesized for a different number of inputs");
+    check_against(exe, assignments(vec![domain.to_vec(); n_inputs]), f)
+}
+
+// Like exhaustively_verify, drawing each input from its declared domain in
+// `spec`. Every input needs a bounded domain.
+pub fn exhaustively_verify_domains(
+    exe: &Execution,
+    spec: &TestSuite,
+    f: impl Fn(&[Value]) -> Value,
+) -> Result<(), CounterExample> {
+    let arity = exe.inputs()[0].len();
+    let values = (0..arity)
+        .map(|input| {
+            let domain = spec.domain(input);
+            assert!(domain != UNBOUNDED, "input {} has no domain to enumerate", input);
+            (domain.0..=domain.1).collect()
+        })
+        .collect();
+
+    check_against(exe, assignments(values), f)
+}
+
+fn check_against(
+    exe: &Execution,
+    assignments: impl Iterator<Item = Vec<Value>>,
+    f: impl Fn(&[Value]) -> Value,
+) -> Result<(), CounterExample> {
+    let instructions = exe.linearize();
+    let mut candidates = (0..exe.output[0].len()).collect::<Vec<_>>();
+
+    for inputs in assignments {
+        let registers = run_on(&instructions, &inputs);
+
+        let expected = f(&inputs);
+        let actual = registers[candidates[0]];
+        candidates.retain(|&reg| registers[reg] == expected);
+        if candidates.is_empty() {
+            return Err(CounterExample {
+                inputs,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exhaustively_verify, CounterExample};
+    use arena::ExecutionArena;
+    use {Execution, Instruction, Register};
+
+    // add r0 r0 agrees with x * x on its one test case, 2, but not on -2
+    #[test]
+    fn exhaustive_check_finds_first_counterexample() {
+        let arena = ExecutionArena::new();
+        let root = arena.alloc_generation(vec![Execution::root(vec![vec![2]])].into_iter().collect());
+        let r0 = Register::new(0);
+        let double = root.iter().next().unwrap().derive(Instruction::Add(r0, r0), &arena);
+
+        assert_eq!(exhaustively_verify(double, &[-2, -1, 0, 1, 2], 1, |x| 2 * x[0]), Ok(()));
+        assert_eq!(
+            exhaustively_verify(double, &[-2, -1, 0, 1, 2], 1, |x| x[0] * x[0]),
+            Err(CounterExample {
+                inputs: vec![-2],
+                expected: 4,
+                actual: -4,
+            })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use rayon::prelude::*;
+
+use indexmap::IndexSet;
+
+use std::collections::{HashMap, HashSet};
+
+use arena::ExecutionArena;
+use memo::MemoTable;
+use synthesizer::Synthesizer;
+use {par_frontier, Execution, Instruction, InstructionVariant, Value};
+
+fn sub(a: Value, b: Value) -> Option<Value> {
+    Some(a.wrapping_sub(b))
+}
+
+fn div(a: Value, b: Value) -> Option<Value> {
+    a.checked_div(b)
+}
+
+fn rem(a: Value, b: Value) -> Option<Value> {
+    a.checked_rem(b)
+}
+
+type BinaryOp = fn(Value, Value) -> Option<Value>;
+
+// Operations the ISA lacks, tried against the frontier to see what they
+// would have added
+const HYPOTHETICAL_INSTRUCTIONS: &[(&str, BinaryOp)] = &[("sub", sub), ("div", div), ("rem", rem)];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub instruction: &'static str,
+    // Register columns it would create that no program in the frontier has
+    pub new_columns: usize,
+    pub reaches_target: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplanationReport {
+    // Distinct executions per generation, starting from the inputs
+    pub frontier_sizes: Vec<usize>,
+    // Programs generated per generation, before deduplication
+    pub generated: Vec<usize>,
+    // Generation where the smallest fraction of generated programs survived
+    pub collapsed_generation: Option<usize>,
+    // Last instructions of the programs deduplicated away in that generation,
+    // most common first
+    pub collapsed_last_instructions: Vec<(InstructionVariant, usize)>,
+    pub suggestions: Vec<Suggestion>,
+    pub solved_at: Option<usize>,
+}
+
+fn columns(exe: &Execution) -> HashSet<Vec<Value>> {
+    (0..exe.output[0].len())
+        .map(|reg| exe.output.iter().map(|row| row[reg]).collect())
+        .collect()
+}
+
+fn suggest(frontier: &IndexSet<Execution>, outputs: &[Value]) -> Vec<Suggestion> {
+    let mut suggestions = HYPOTHETICAL_INSTRUCTIONS
+        .iter()
+        .map(|&(name, op)| {
+            let (new_columns, reaches_target) = par_frontier(frontier)
+                .map(|exe| {
+                    let existing = columns(exe);
+                    let register_count = exe.output[0].len();
+                    let mut new_columns = 0;
+                    let mut reaches_target = false;
+
+                    for r1 in 0..register_count {
+                        for r2 in 0..register_count {
+                            let column = exe.output
+                                .iter()
+                                .map(|row| op(row[r1], row[r2]))
+                                .collect::<Option<Vec<_>>>();
+                            if let Some(column) = column {
+                                if !existing.contains(&column) {
+                                    new_columns += 1;
+                                    reaches_target |= column == outputs;
+                                }
+                            }
+                        }
+                    }
+
+                    (new_columns, reaches_target)
+                })
+                .reduce(|| (0, false), |a, b| (a.0 + b.0, a.1 || b.1));
+
+            Suggestion {
+                instruction: name,
+                new_columns,
+                reaches_target,
+            }
+        })
+        .filter(|suggestion| suggestion.new_columns > 0)
+        .collect::<Vec<_>>();
+
+    suggestions.sort_by(|a, b| {
+        (b.reaches_target, b.new_columns).cmp(&(a.reaches_target, a.new_columns))
+    });
+    suggestions
+}
+
+impl Synthesizer {
+    // Run BFS for up to `depth` generations, recording where the search
+    // stops making progress. Generations are built and checked as the
+    // search builds and checks them, with the builder's filters, overflow
+    // and verification policies, but without dedup lookback or pruning.
+    pub fn explain(&self, depth: usize) -> ExplanationReport {
+        self.config.install(|| {
+            let arena = ExecutionArena::new();
+            let memo = MemoTable::new();
+            let mut starts = IndexSet::new();
+            starts.insert(self.start(&arena));
+            let mut frontier = arena.alloc_generation(starts);
+
+            let mut report = ExplanationReport {
+                frontier_sizes: vec![1],
+                generated: vec![0],
+                collapsed_generation: None,
+                collapsed_last_instructions: vec![],
+                suggestions: vec![],
+                solved_at: None,
+            };
+            if frontier.iter().any(|exe| self.output_register(exe).is_some()) {
+                report.solved_at = Some(0);
+                return report;
+            }
+
+            let mut collapsed = frontier;
+            let mut worst_survival = f64::INFINITY;
+
+            for generation in 1..=depth {
+                let new_executions = self.children(frontier, &memo).collect::<Vec<_>>();
+                let generated = new_executions.len();
+
+                let mut dropped = HashMap::new();
+                let mut next = IndexSet::with_capacity(generated);
+                for exe in new_executions {
+                    let variant = exe.program.instruction.as_ref().map(Instruction::variant);
+                    if !next.insert(exe) {
+                        *dropped.entry(variant.unwrap()).or_insert(0) += 1;
+                    }
+                }
+
+                report.frontier_sizes.push(next.len());
+                report.generated.push(generated);
+                frontier = arena.alloc_generation(next);
+
+                let survival = frontier.len() as f64 / generated as f64;
+                if survival < worst_survival {
+                    worst_survival = survival;
+                    collapsed = frontier;
+                    report.collapsed_generation = Some(generation);
+                    report.collapsed_last_instructions = dropped.into_iter().collect();
+                }
+
+                if par_frontier(frontier).any(|exe| self.output_register(exe).is_some()) {
+                    report.solved_at = Some(generation);
+                    break;
+                }
+            }
+
+            report.collapsed_last_instructions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            report.suggestions = suggest(collapsed, &self.outputs);
+            report
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testsuite::{TestCase, TestSuite};
+    use {InstructionVariant, SynthesizerBuilder};
+
+    fn negate() -> TestSuite {
+        TestSuite::new(vec![TestCase::new(vec![3], -3), TestCase::new(vec![-2], 2), TestCase::new(vec![5], -5)])
+    }
+
+    // The report describes the builder's search space, not every program
+    #[test]
+    fn explain_follows_enabled_instructions() {
+        let report = SynthesizerBuilder::new().build(&negate()).explain(3);
+        assert_eq!(report.solved_at, Some(1));
+
+        let adds_only = SynthesizerBuilder::new().with_enabled_instructions(vec![InstructionVariant::Add]);
+        let report = adds_only.build(&negate()).explain(3);
+        assert_eq!(report.solved_at, None);
+        assert_eq!(report.frontier_sizes, vec![1, 1, 1, 1]);
+    }
+}
@@ -0,0 +1,64 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+use {Execution, Instruction};
+
+// A run of instructions the search treats as one, so a common idiom costs a
+// single step of depth. Applying it applies each part in order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct FusedInstruction(pub Vec<Instruction>);
+
+// `instructions` with every fused instruction replaced by its parts, for
+// code that only knows the plain variants
+pub fn flatten_fused(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut flat = Vec::with_capacity(instructions.len());
+    for inst in instructions {
+        match *inst {
+            Instruction::Fused(ref fused) => flat.extend(flatten_fused(&fused.0)),
+            _ => flat.push(inst.clone()),
+        }
+    }
+    flat
+}
+
+// Counts adjacent instruction pairs across the solutions it is shown, to
+// suggest atoms for SynthesizerBuilder::with_fused_atoms
+#[derive(Debug, Clone, Default)]
+pub struct FusionPass {
+    counts: HashMap<FusedInstruction, usize>,
+    min_count: usize,
+}
+
+impl FusionPass {
+    // Pairs become candidates once seen `min_count` times
+    pub fn new(min_count: usize) -> FusionPass {
+        FusionPass {
+            counts: HashMap::new(),
+            min_count,
+        }
+    }
+
+    // Adds the pairs of neighbouring instructions in `exe`, with fused ones
+    // flattened first, to the counts, and returns every pair seen at least
+    // `min_count` times so far, most common first. Ties are in instruction
+    // order, so the result does not depend on hashing.
+    pub fn detect_fusion_candidates(&mut self, exe: &Execution) -> Vec<FusedInstruction> {
+        let instructions = flatten_fused(&exe.linearize());
+        for pair in instructions.windows(2) {
+            *self.counts.entry(FusedInstruction(pair.to_vec())).or_insert(0) += 1;
+        }
+
+        let mut candidates = self
+            .counts
+            .iter()
+            .filter(|&(_, &count)| count >= self.min_count)
+            .map(|(pair, &count)| (Reverse(count), pair))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.into_iter().map(|(_, pair)| pair.clone()).collect()
+    }
+}
@@ -0,0 +1,99 @@
+use rand::Rng;
+
+use mutation::mutate;
+use rng::{SearchRng, SharedRng};
+use strategy::{CancellationToken, SearchStrategy};
+use testsuite::TestSuite;
+use {score, verify_output};
+use {Instruction, OwnedExecution, RegisterIndex};
+
+// Single-point crossover: the head of `a` up to the cut, then the tail of
+// `b` after it. The shorter program is treated as padded with nothing, so
+// the cut can land past its end.
+pub fn crossover(a: &[Instruction], b: &[Instruction], rng: &mut impl Rng) -> Vec<Instruction> {
+    let point = rng.gen_range(0..=a.len().max(b.len()));
+
+    a[..point.min(a.len())]
+        .iter()
+        .chain(b[point.min(b.len())..].iter())
+        .cloned()
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct GASearchStrategy {
+    population: usize,
+    generations: usize,
+    rng: Option<SharedRng>,
+}
+
+impl GASearchStrategy {
+    pub fn new(population: usize, generations: usize) -> GASearchStrategy {
+        GASearchStrategy {
+            population,
+            generations,
+            rng: None,
+        }
+    }
+
+    pub fn with_rng(mut self, rng: SharedRng) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+}
+
+impl SearchStrategy for GASearchStrategy {
+    fn run(
+        &self,
+        spec: &TestSuite,
+        cancel: CancellationToken,
+    ) -> Option<(OwnedExecution, RegisterIndex)> {
+        let rng = &mut SearchRng::from_config(&self.rng);
+        let inputs = spec.inputs();
+        let outputs = &spec.outputs();
+        let start = OwnedExecution::replay(inputs.clone(), vec![]);
+        if let Some(reg) = verify_output(&start.output, outputs) {
+            return Some((start, reg));
+        }
+
+        let by_score = |a: &OwnedExecution, b: &OwnedExecution| {
+            score(&b.output, outputs).total_cmp(&score(&a.output, outputs))
+        };
+
+        let mut population = (0..self.population).map(|_| mutate(&start, rng)).collect::<Vec<_>>();
+
+        for _ in 0..self.generations {
+            if cancel.is_cancelled() {
+                return None;
+            }
+
+            if let Some((exe, reg)) = population
+                .iter()
+                .filter_map(|exe| verify_output(&exe.output, outputs).map(|reg| (exe, reg)))
+                .next()
+            {
+                return Some((exe.clone(), reg));
+            }
+
+            population.sort_by(by_score);
+            population.truncate(self.population.div_ceil(2));
+
+            let mut offspring = Vec::new();
+            for (i, a) in population.iter().enumerate() {
+                for b in &population[i + 1..] {
+                    let child = crossover(&a.instructions, &b.instructions, rng);
+                    // Crossover can splice in reads of registers the new head never creates
+                    if let Some(child) = OwnedExecution::try_replay(inputs.clone(), child) {
+                        offspring.push(mutate(&child, rng));
+                    }
+                }
+            }
+
+            population.extend(offspring);
+            population.sort_by(by_score);
+            population.truncate(self.population);
+        }
+
+        None
+    }
+}
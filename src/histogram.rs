@@ -0,0 +1,69 @@
+use indexmap::IndexSet;
+
+use std::collections::HashMap;
+
+use {Execution, Instruction, InstructionVariant, OwnedExecution};
+
+pub fn instruction_histogram(solutions: &[OwnedExecution]) -> HashMap<InstructionVariant, usize> {
+    let mut histogram = HashMap::new();
+    for inst in solutions.iter().flat_map(|exe| exe.instructions.iter()) {
+        *histogram.entry(inst.variant()).or_insert(0) += 1;
+    }
+    histogram
+}
+
+// How many programs in `frontier` end with each kind of instruction
+pub fn last_instruction_histogram(frontier: &IndexSet<Execution>) -> HashMap<InstructionVariant, usize> {
+    let mut histogram = HashMap::new();
+    for inst in frontier.iter().filter_map(|exe| exe.program.instruction.as_ref()) {
+        *histogram.entry(inst.variant()).or_insert(0) += 1;
+    }
+    histogram
+}
+
+// Stops generating a kind of instruction once it has ended more than
+// `threshold` of the frontier for `generations` generations in a row, all
+// without a solution, for SynthesizerBuilder::
+// with_instruction_histogram_pruning
+#[derive(Debug, Clone)]
+pub(crate) struct HistogramPruner {
+    threshold: f64,
+    generations: usize,
+    streaks: HashMap<InstructionVariant, usize>,
+    pruned: Vec<InstructionVariant>,
+}
+
+impl HistogramPruner {
+    pub(crate) fn new(threshold: f64, generations: usize) -> HistogramPruner {
+        HistogramPruner {
+            threshold,
+            generations,
+            streaks: HashMap::new(),
+            pruned: vec![],
+        }
+    }
+
+    // Counts a generation that did not solve the spec
+    pub(crate) fn observe(&mut self, frontier: &IndexSet<Execution>) {
+        let total = frontier.len() as f64;
+        let dominant = last_instruction_histogram(frontier)
+            .into_iter()
+            .filter(|&(_, count)| count as f64 > self.threshold * total)
+            .map(|(variant, _)| variant)
+            .collect::<Vec<_>>();
+
+        self.streaks.retain(|variant, _| dominant.contains(variant));
+        for variant in dominant {
+            let streak = self.streaks.entry(variant).or_insert(0);
+            *streak += 1;
+            if *streak >= self.generations && !self.pruned.contains(&variant) {
+                search_log!(info, "pruning {:?} after {} generations ending most programs", variant, streak);
+                self.pruned.push(variant);
+            }
+        }
+    }
+
+    pub(crate) fn allows(&self, inst: &Instruction) -> bool {
+        !self.pruned.contains(&inst.variant())
+    }
+}
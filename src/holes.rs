@@ -0,0 +1,131 @@
+use indexmap::IndexSet;
+
+use std::collections::HashSet;
+
+use arena::ExecutionArena;
+use memo::MemoTable;
+use synthesizer::{SynthesisError, Synthesizer, SynthesizerBuilder};
+use testsuite::TestSuite;
+use {Execution, Instruction, Program, Value};
+
+// A program with some instructions left out. Each None is a hole for any
+// one instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HoledProgram(pub Vec<Option<Instruction>>);
+
+struct Filler<'a> {
+    synthesizer: &'a Synthesizer,
+    arena: &'a ExecutionArena<'a>,
+    memo: MemoTable,
+    template: &'a [Option<Instruction>],
+    // Positions and register files already found to lead nowhere
+    dead_ends: HashSet<(usize, Vec<Vec<Value>>)>,
+}
+
+impl<'a> Filler<'a> {
+    // Completes the program from `position` on, with `exe` the program so
+    // far, pushing onto `filled`
+    fn fill(&mut self, position: usize, exe: &'a Execution<'a>, filled: &mut Vec<Instruction>) -> bool {
+        if position == self.template.len() {
+            return self.synthesizer.output_register(exe).is_some();
+        }
+        if self.dead_ends.contains(&(position, exe.output.clone())) {
+            return false;
+        }
+
+        let programs = match self.template[position] {
+            Some(ref inst) if inst.is_valid_for(exe.output[0].len()) => vec![Program {
+                parent: Some(exe),
+                instruction: Some(inst.clone()),
+                depth: exe.depth() + 1,
+            }],
+            Some(_) => Vec::new(),
+            None => self.synthesizer.expand(exe, None),
+        };
+        let children = programs
+            .into_iter()
+            .filter_map(|program| self.synthesizer.execute_child(program, &self.memo))
+            .filter(|child| self.synthesizer.within_domains(child))
+            .collect::<IndexSet<_>>();
+        let arena = self.arena;
+        for child in arena.alloc_generation(children) {
+            filled.push(child.program.instruction.clone().unwrap());
+            if self.fill(position + 1, child, filled) {
+                return true;
+            }
+            filled.pop();
+        }
+
+        self.dead_ends.insert((position, exe.output.clone()));
+        false
+    }
+}
+
+impl Synthesizer {
+    // The first filling of the holes in `template`, trying each hole's
+    // candidates in the order the search generates them, that solves `spec`
+    // with the result in a register `builder` accepts. Holes draw from the
+    // instructions `builder` allows, and every instruction runs under its
+    // overflow policy and domains. Only the holes are searched, so a mostly
+    // known program is quick to finish. Fixed instructions that read a
+    // register the program has not created yet rule out the filling. The
+    // template is the whole program, so no preamble is run before it.
+    pub fn fill_holes(
+        template: HoledProgram,
+        spec: &TestSuite,
+        builder: &SynthesizerBuilder,
+    ) -> Result<Vec<Instruction>, SynthesisError> {
+        let synthesizer = builder.build(spec);
+        synthesizer.check_spec()?;
+
+        let arena = ExecutionArena::new();
+        let root = arena.alloc_generation(vec![synthesizer.root()].into_iter().collect());
+        let mut filler = Filler {
+            synthesizer: &synthesizer,
+            arena: &arena,
+            memo: MemoTable::new(),
+            template: &template.0,
+            dead_ends: HashSet::new(),
+        };
+        let mut filled = Vec::with_capacity(template.0.len());
+        if filler.fill(0, root.iter().next().unwrap(), &mut filled) {
+            Ok(filled)
+        } else {
+            Err(SynthesisError::NoFilling)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HoledProgram;
+    use synthesizer::SynthesisError;
+    use testsuite::{TestCase, TestSuite};
+    use {Instruction, InstructionVariant, Register, Synthesizer, SynthesizerBuilder};
+
+    // x * x + x with the squaring left out
+    fn square_plus_input() -> (HoledProgram, TestSuite) {
+        let r = Register::new;
+        let template = HoledProgram(vec![Some(Instruction::Mov(r(0), r(1))), None, Some(Instruction::Add(r(1), r(0)))]);
+        let cases = vec![TestCase::new(vec![2], 6), TestCase::new(vec![3], 12), TestCase::new(vec![-1], 0)];
+        (template, TestSuite::new(cases))
+    }
+
+    #[test]
+    fn fills_the_hole() {
+        let (template, spec) = square_plus_input();
+        let filled = Synthesizer::fill_holes(template.clone(), &spec, &SynthesizerBuilder::new()).unwrap();
+        assert_eq!(filled.len(), 3);
+        assert_eq!(Some(filled[0].clone()), template.0[0]);
+        assert_eq!(filled[1].variant(), InstructionVariant::Mul);
+        assert_eq!(Some(filled[2].clone()), template.0[2]);
+    }
+
+    #[test]
+    fn holes_follow_enabled_instructions() {
+        let (template, spec) = square_plus_input();
+        let no_mul = SynthesizerBuilder::new()
+            .with_enabled_instructions(vec![InstructionVariant::Mov, InstructionVariant::Add, InstructionVariant::Neg]);
+        assert_eq!(Synthesizer::fill_holes(template, &spec, &no_mul), Err(SynthesisError::NoFilling));
+    }
+}
@@ -0,0 +1,83 @@
+use testsuite::{Domain, UNBOUNDED};
+use {Instruction, Value};
+
+// Bounds on one register across every in-domain input, or None once nothing
+// useful is known
+pub type Interval = Option<Domain>;
+
+fn add(a: Domain, b: Domain) -> Option<Domain> {
+    Some((a.0.checked_add(b.0)?, a.1.checked_add(b.1)?))
+}
+
+fn mul(a: Domain, b: Domain) -> Option<Domain> {
+    let corners = [
+        a.0.checked_mul(b.0)?,
+        a.0.checked_mul(b.1)?,
+        a.1.checked_mul(b.0)?,
+        a.1.checked_mul(b.1)?,
+    ];
+    Some((*corners.iter().min().unwrap(), *corners.iter().max().unwrap()))
+}
+
+fn neg(a: Domain) -> Option<Domain> {
+    Some((a.1.checked_neg()?, a.0.checked_neg()?))
+}
+
+// Apply `inst` to the register intervals. A result that could leave the
+// range of Value is no longer known.
+fn apply(inst: &Instruction, intervals: &mut Vec<Interval>) {
+    let binary = |f: fn(Domain, Domain) -> Option<Domain>, a: Interval, b: Interval| f(a?, b?);
+
+    let (reg, interval) = match *inst {
+        Instruction::Mov(r1, r2) => {
+            let value = intervals[r1.as_index()];
+            if r2.as_index() == intervals.len() {
+                intervals.push(value);
+            } else {
+                intervals[r2.as_index()] = value;
+            }
+            return;
+        }
+        Instruction::Add(r1, r2) => (r2, binary(add, intervals[r1.as_index()], intervals[r2.as_index()])),
+        Instruction::Mul(r1, r2) => (r2, binary(mul, intervals[r1.as_index()], intervals[r2.as_index()])),
+        Instruction::Neg(r) => (r, intervals[r.as_index()].and_then(neg)),
+        Instruction::Fused(ref fused) => {
+            for inst in &fused.0 {
+                apply(inst, intervals);
+            }
+            return;
+        }
+    };
+    intervals[reg.as_index()] = interval;
+}
+
+// Intervals for registers that start out holding `domains`. Unbounded
+// inputs are never pruned on.
+pub fn input_intervals(domains: &[Domain]) -> Vec<Interval> {
+    domains
+        .iter()
+        .map(|&domain| if domain == UNBOUNDED { None } else { Some(domain) })
+        .collect()
+}
+
+// The intervals after `inst` runs on registers with `intervals`. An
+// execution carries none when the search has no domains, and so do its
+// children.
+pub(crate) fn step(inst: &Instruction, intervals: &[Interval]) -> Vec<Interval> {
+    let mut intervals = intervals.to_vec();
+    if !intervals.is_empty() {
+        apply(inst, &mut intervals);
+    }
+    intervals
+}
+
+// Whether some register lies outside `bounds` for every in-domain input,
+// so that the program can only go on through an out-of-domain
+// intermediate
+pub fn leaves_domain(intervals: &[Interval], bounds: Domain) -> bool {
+    intervals.iter().flatten().any(|&(lo, hi)| hi < bounds.0 || lo > bounds.1)
+}
+
+pub fn in_domain(value: Value, domain: Domain) -> bool {
+    domain.0 <= value && value <= domain.1
+}
@@ -0,0 +1,116 @@
+use indexmap::IndexSet;
+
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use monitor::{SynthesizerMonitor, TerminationReason};
+use synthesizer::{SynthesisError, SynthesisResult, SynthesizerBuilder};
+use testsuite::TestSuite;
+use {Execution, RegisterIndex};
+
+// Hands a line per generation to the thread writing the log
+#[derive(Debug)]
+struct JsonLinesMonitor {
+    lines: Mutex<Sender<String>>,
+    started: Instant,
+    executions: AtomicUsize,
+}
+
+impl SynthesizerMonitor for JsonLinesMonitor {
+    fn on_executions(&self, _gen: usize, executions: usize) {
+        self.executions.store(executions, Ordering::Relaxed);
+    }
+
+    fn on_generation(&self, gen: usize, frontier: &IndexSet<Execution>) {
+        let line = format!(
+            "{{\"gen\": {}, \"frontier\": {}, \"time_ms\": {}, \"executions_run\": {}}}",
+            gen,
+            frontier.len(),
+            self.started.elapsed().as_millis(),
+            self.executions.load(Ordering::Relaxed)
+        );
+        // Once writing has failed nobody is listening
+        let _ = self.lines.lock().unwrap().send(line);
+    }
+
+    fn on_solution(&self, _exe: &Execution, _reg: RegisterIndex) {}
+
+    fn on_termination(&self, _reason: TerminationReason) {}
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Runs `builder` on `spec`, writing one JSON object per line to
+// `log_writer` as the search goes:
+//
+//   {"gen": 3, "frontier": 1024, "time_ms": 450, "executions_run": 8192}
+//
+// for every breadth-first generation, with time_ms counted from the start
+// of the run, then one last line
+//
+//   {"found": true, "depth": 4, "program": "mov r0 r2; ...", "register": 2}
+//
+// or {"found": false}, with an "error" field when the search failed. The
+// search runs on another thread so lines are written as they happen. With
+// random restarts every restart logs its generations.
+pub fn run_with_logging<W: Write>(
+    spec: &TestSuite,
+    builder: SynthesizerBuilder,
+    log_writer: &mut W,
+) -> SynthesisResult {
+    let (sender, lines) = mpsc::channel();
+    let monitor = JsonLinesMonitor {
+        lines: Mutex::new(sender),
+        started: Instant::now(),
+        executions: AtomicUsize::new(0),
+    };
+    let synthesizer = builder.with_monitor(Arc::new(monitor)).build(spec);
+
+    let (result, mut written) = thread::scope(|scope| {
+        // Dropping the synthesizer drops the sender and ends `lines`
+        let search = scope.spawn(move || synthesizer.run());
+        let mut written = Ok(());
+        for line in lines {
+            written = writeln!(log_writer, "{}", line);
+            if written.is_err() {
+                break;
+            }
+        }
+        (search.join().unwrap(), written)
+    });
+
+    if written.is_ok() {
+        written = match result {
+            Ok(Some((ref exe, reg))) => writeln!(
+                log_writer,
+                "{{\"found\": true, \"depth\": {}, \"program\": {}, \"register\": {}}}",
+                exe.instructions.len(),
+                json_string(&exe.to_string().trim_start().replace('\n', "; ")),
+                reg
+            ),
+            Ok(None) => writeln!(log_writer, "{{\"found\": false}}"),
+            Err(ref e) => writeln!(log_writer, "{{\"found\": false, \"error\": {}}}", json_string(&e.to_string())),
+        };
+    }
+    let found = result?;
+    written.map_err(|e| SynthesisError::LogWrite(e.kind()))?;
+    Ok(found)
+}
@@ -0,0 +1,1218 @@
+extern crate typed_arena;
+
+extern crate indexmap;
+
+extern crate rayon;
+
+extern crate rand;
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+#[cfg(feature = "indicatif")]
+extern crate indicatif;
+
+#[cfg(feature = "log")]
+extern crate log;
+
+#[cfg(feature = "serialization")]
+extern crate serde;
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "config")]
+extern crate toml;
+
+#[cfg(feature = "cache")]
+extern crate sha2;
+#[cfg(feature = "cache")]
+extern crate sled;
+
+use rayon::prelude::*;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+// `log::$level!` with the `log` feature. Without it the arguments are
+// still type-checked but never formatted.
+#[cfg(feature = "log")]
+macro_rules! search_log {
+    ($level:ident, $($arg:tt)+) => {
+        ::log::$level!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! search_log {
+    ($level:ident, $($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+pub mod approximate;
+pub mod arena;
+pub mod batch;
+pub mod boolean;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod dataflow;
+pub mod dedup;
+pub mod diversity;
+pub mod encoding;
+pub mod equivalence;
+mod estimate;
+pub mod exhaustive;
+pub mod explain;
+pub mod fusion;
+pub mod genetic;
+pub mod histogram;
+pub mod holes;
+pub mod interval;
+pub mod jsonlog;
+pub mod mcmc;
+pub mod memo;
+pub mod memory;
+pub mod minimize;
+pub mod monitor;
+pub mod mutation;
+pub mod oracle;
+pub mod portfolio;
+pub mod printer;
+pub mod profile;
+mod progress;
+pub mod rng;
+pub mod sampling;
+pub mod session;
+pub mod spec;
+pub mod state;
+pub mod store;
+pub mod strategy;
+pub mod synthesizer;
+pub mod testsuite;
+pub mod validate;
+pub use indexmap::IndexSet;
+
+pub use approximate::ApproximateResult;
+pub use arena::ExecutionArena;
+pub use batch::batch_synthesize;
+pub use boolean::{bool_canonicalize, BoolInstruction, BooleanExecutor, BooleanSynthesizer, BooleanVerifier};
+#[cfg(feature = "cache")]
+pub use cache::{CachedSolution, SynthesisCache};
+#[cfg(feature = "config")]
+pub use config::SynthesizerConfig;
+pub use dataflow::register_dependency_graph;
+pub use dedup::{verify_dedup_soundness, DedupError};
+pub use diversity::{most_diverse_subset, pairwise_edit_distance};
+pub use encoding::{DecodeError, EncodeError, FixedWidthEncoder, InstructionEncoder, VarIntEncoder};
+pub use equivalence::{group_by_output, representatives, EquivalenceClass, OutputMatrix};
+pub use estimate::HardnessEstimate;
+pub use exhaustive::{differential_test, exhaustively_verify, exhaustively_verify_domains, verify_counterexample};
+pub use exhaustive::{CounterExample, DiffTestResult};
+pub use explain::{ExplanationReport, Suggestion};
+pub use fusion::{flatten_fused, FusedInstruction, FusionPass};
+pub use genetic::GASearchStrategy;
+pub use histogram::{instruction_histogram, last_instruction_histogram};
+pub use holes::HoledProgram;
+pub use interval::Interval;
+pub use jsonlog::run_with_logging;
+pub use mcmc::MCMCSearchStrategy;
+pub use memo::MemoTable;
+pub use memory::MemoryReport;
+pub use minimize::{find_minimal_register_count, verify_is_minimal, TestCaseMinimizer};
+pub use monitor::{LoggingMonitor, MonitorEvent, RecordingMonitor, SynthesizerMonitor, TerminationReason};
+pub use mutation::MutationSynthesizer;
+pub use oracle::{EquivalenceOracle, ExhaustiveOracle, OracleVerifiedSolution};
+pub use portfolio::SynthesizerPortfolio;
+pub use printer::{pretty_print, AsmTarget, OutputFormat};
+pub use printer::{DotPrinter, JsonPrinter, ProgramPrinter, TextPrinter, WatPrinter, X86Printer};
+pub use profile::{profile_execute, profile_generation, ProfileReport, TimingProfile};
+pub use rng::SharedRng;
+pub use sampling::RandomSamplingStrategy;
+pub use session::SynthesizerSession;
+pub use spec::{detect_aliased_inputs, SpecError, SpecWarning, SynthesisSpecBuilder};
+pub use state::{GenerationResult, SynthesizerState};
+pub use store::{hash_program, ProgramStore};
+pub use strategy::{CancellationToken, SearchStrategy};
+pub use synthesizer::{Strategy, SynthesisError, SynthesisResult, Synthesizer, SynthesizerBuilder};
+pub use testsuite::{Domain, TestCase, TestSuite};
+pub use validate::{validate_program, ProgramValidationError};
+
+// Registers a program may have; one more than the compact encoding's
+// one-byte operands can name would be unencodable
+pub const MAX_REGISTERS: usize = 256;
+
+// Keeps register operands apart from counts and other plain indices
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct Register(usize);
+
+impl Register {
+    pub fn new(index: usize) -> Register {
+        Register(index)
+    }
+
+    // The register `offset` places further along, if it is below MAX_REGISTERS
+    pub fn checked_add(self, offset: usize) -> Option<Register> {
+        self.0.checked_add(offset).filter(|&index| index < MAX_REGISTERS).map(Register)
+    }
+
+    pub fn as_index(self) -> usize {
+        self.0
+    }
+}
+
+impl std::ops::Add<usize> for Register {
+    type Output = Register;
+
+    fn add(self, offset: usize) -> Register {
+        self.checked_add(offset)
+            .unwrap_or_else(|| panic!("r{} + {} is past the last of {} registers", self, offset, MAX_REGISTERS))
+    }
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub type RegisterIndex = Register;
+pub type Value = isize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum Instruction {
+    Mov(RegisterIndex, RegisterIndex),
+    Add(RegisterIndex, RegisterIndex),
+    Mul(RegisterIndex, RegisterIndex),
+    //Sub(RegisterIndex, RegisterIndex),
+    Neg(RegisterIndex),
+    // Never generated by add_one_instruction; see
+    // SynthesizerBuilder::with_fused_atoms
+    Fused(FusedInstruction),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum InstructionVariant {
+    Mov,
+    Add,
+    Mul,
+    Neg,
+    Fused,
+}
+
+// The variants add_one_instruction generates, in declaration order. Fused
+// instructions only come from SynthesizerBuilder::with_fused_atoms.
+pub const ALL_INSTRUCTION_VARIANTS: [InstructionVariant; 4] = [
+    InstructionVariant::Mov,
+    InstructionVariant::Add,
+    InstructionVariant::Mul,
+    InstructionVariant::Neg,
+];
+
+impl Instruction {
+    pub fn variant(&self) -> InstructionVariant {
+        match *self {
+            Instruction::Mov(..) => InstructionVariant::Mov,
+            Instruction::Add(..) => InstructionVariant::Add,
+            Instruction::Mul(..) => InstructionVariant::Mul,
+            Instruction::Neg(..) => InstructionVariant::Neg,
+            Instruction::Fused(..) => InstructionVariant::Fused,
+        }
+    }
+
+    pub fn apply(&self, testcase: &mut Vec<Value>) {
+        match *self {
+            Instruction::Mov(r1, r2) => if testcase.len() <= r2.as_index() {
+                let temp = testcase[r1.as_index()];
+                testcase.push(temp);
+            } else {
+                testcase[r2.as_index()] = testcase[r1.as_index()];
+            },
+            Instruction::Add(r1, r2) => {
+                let (r1, r2) = (r1.as_index(), r2.as_index());
+                testcase[r2] = testcase[r1].wrapping_add(testcase[r2])
+            }
+            Instruction::Mul(r1, r2) => {
+                let (r1, r2) = (r1.as_index(), r2.as_index());
+                testcase[r2] = testcase[r1].wrapping_mul(testcase[r2])
+            }
+            //Instruction::Sub(r1, r2) => testcase[r2] = testcase[r1].wrapping_add(-testcase[r2]),
+            Instruction::Neg(r) => testcase[r.as_index()] = testcase[r.as_index()].wrapping_mul(-1),
+            Instruction::Fused(ref fused) => {
+                for inst in &fused.0 {
+                    inst.apply(testcase);
+                }
+            }
+        }
+    }
+
+    // Like apply, with arithmetic following `policy`. False, with the test
+    // case partly updated, if the policy is Checked and something
+    // overflowed.
+    pub fn apply_with(&self, testcase: &mut Vec<Value>, policy: OverflowPolicy) -> bool {
+        if policy == OverflowPolicy::Wrapping {
+            self.apply(testcase);
+            return true;
+        }
+        let (reg, value) = match *self {
+            Instruction::Mov(..) => {
+                self.apply(testcase);
+                return true;
+            }
+            Instruction::Add(r1, r2) => (r2, policy.add(testcase[r1.as_index()], testcase[r2.as_index()])),
+            Instruction::Mul(r1, r2) => (r2, policy.mul(testcase[r1.as_index()], testcase[r2.as_index()])),
+            Instruction::Neg(r) => (r, policy.neg(testcase[r.as_index()])),
+            Instruction::Fused(ref fused) => return fused.0.iter().all(|inst| inst.apply_with(testcase, policy)),
+        };
+        match value {
+            Some(value) => {
+                testcase[reg.as_index()] = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The highest register this reads or writes. Panics on an empty fused
+    // instruction.
+    pub fn max_register(&self) -> RegisterIndex {
+        match *self {
+            Instruction::Mov(r1, r2) | Instruction::Add(r1, r2) | Instruction::Mul(r1, r2) => r1.max(r2),
+            Instruction::Neg(r) => r,
+            Instruction::Fused(ref fused) => fused
+                .0
+                .iter()
+                .map(Instruction::max_register)
+                .max()
+                .expect("an empty fused instruction uses no registers"),
+        }
+    }
+
+    // Whether every operand exists in a file of `register_count` registers,
+    // allowing Mov to create the next one
+    pub fn is_valid_for(&self, register_count: usize) -> bool {
+        match *self {
+            Instruction::Mov(r1, r2) => r1.as_index() < register_count && r2.as_index() <= register_count,
+            Instruction::Add(..) | Instruction::Mul(..) | Instruction::Neg(..) => {
+                self.max_register().as_index() < register_count
+            }
+            Instruction::Fused(ref fused) => fused
+                .0
+                .iter()
+                .try_fold(register_count, |count, inst| {
+                    if inst.is_valid_for(count) {
+                        Some(inst.register_count_after(count))
+                    } else {
+                        None
+                    }
+                })
+                .is_some(),
+        }
+    }
+
+    pub fn register_count_after(&self, register_count: usize) -> usize {
+        match *self {
+            Instruction::Mov(_, r2) if r2.as_index() == register_count => register_count + 1,
+            Instruction::Fused(ref fused) => {
+                fused.0.iter().fold(register_count, |count, inst| inst.register_count_after(count))
+            }
+            _ => register_count,
+        }
+    }
+
+    // The register this instruction writes; for a fused one, the register
+    // its last part writes. Panics on an empty fused instruction.
+    pub fn destination(&self) -> RegisterIndex {
+        match *self {
+            Instruction::Mov(_, r2) | Instruction::Add(_, r2) | Instruction::Mul(_, r2) => r2,
+            Instruction::Neg(r) => r,
+            Instruction::Fused(ref fused) => {
+                fused.0.last().expect("an empty fused instruction writes nothing").destination()
+            }
+        }
+    }
+
+    // Whether this instruction writes `reg`, in any of its parts if fused
+    pub fn affects_register(&self, reg: RegisterIndex) -> bool {
+        match *self {
+            Instruction::Fused(ref fused) => fused.0.iter().any(|inst| inst.affects_register(reg)),
+            _ => self.destination() == reg,
+        }
+    }
+
+    // Appends the registers this writes that `ordering` lacks, in order
+    fn push_writes(&self, ordering: &mut Vec<RegisterIndex>) {
+        match *self {
+            Instruction::Fused(ref fused) => {
+                for inst in &fused.0 {
+                    inst.push_writes(ordering);
+                }
+            }
+            _ => {
+                if !ordering.contains(&self.destination()) {
+                    ordering.push(self.destination());
+                }
+            }
+        }
+    }
+
+    // Whether applying this to one test case's registers `state` changes
+    // nothing, as for `add r1 r2` with r1 zero or `mul r1 r2` with r1 one.
+    // A Mov that creates a register always changes the state. Arithmetic
+    // wraps; see is_identity_with for the other overflow policies.
+    pub fn is_identity(&self, state: &[Value]) -> bool {
+        let value = |reg: RegisterIndex| state[reg.as_index()];
+        match *self {
+            Instruction::Mov(_, r2) if r2.as_index() >= state.len() => false,
+            Instruction::Mov(r1, r2) => value(r1) == value(r2),
+            Instruction::Add(r1, r2) => value(r2).wrapping_add(value(r1)) == value(r2),
+            Instruction::Mul(r1, r2) => value(r2).wrapping_mul(value(r1)) == value(r2),
+            Instruction::Neg(r) => value(r).wrapping_mul(-1) == value(r),
+            Instruction::Fused(..) => {
+                let mut after = state.to_vec();
+                self.apply(&mut after);
+                after == state
+            }
+        }
+    }
+
+    // Like is_identity, with arithmetic following `policy`. An instruction
+    // that overflows under Checked is not an identity.
+    pub fn is_identity_with(&self, state: &[Value], policy: OverflowPolicy) -> bool {
+        let value = |reg: RegisterIndex| state[reg.as_index()];
+        let unchanged = |reg: RegisterIndex, new: Option<Value>| new == Some(value(reg));
+        match *self {
+            _ if policy == OverflowPolicy::Wrapping => self.is_identity(state),
+            Instruction::Mov(..) => self.is_identity(state),
+            Instruction::Add(r1, r2) => unchanged(r2, policy.add(value(r1), value(r2))),
+            Instruction::Mul(r1, r2) => unchanged(r2, policy.mul(value(r1), value(r2))),
+            Instruction::Neg(r) => unchanged(r, policy.neg(value(r))),
+            Instruction::Fused(..) => {
+                let mut after = state.to_vec();
+                self.apply_with(&mut after, policy) && after == state
+            }
+        }
+    }
+
+    // The register files `apply` turns into `post_state` without wrapping,
+    // found by solving for `modified_reg`, the register this instruction
+    // writes. Empty when there are none, and also when the old value was
+    // lost entirely: a Mov over an existing register or a Mul by zero
+    // leaves it unknown, which a backward search must track itself. A Mov
+    // into the last register is taken to have created it. Fused
+    // instructions are not inverted.
+    pub fn inverse(&self, post_state: &[Value], modified_reg: RegisterIndex) -> Vec<Vec<Value>> {
+        assert!(self.affects_register(modified_reg), "{:?} does not write r{}", self, modified_reg);
+        let post = |reg: RegisterIndex| post_state[reg.as_index()];
+        let with = |value: Value| {
+            let mut pre = post_state.to_vec();
+            pre[modified_reg.as_index()] = value;
+            pre
+        };
+
+        match *self {
+            Instruction::Mov(r1, r2) if r1 == r2 => vec![post_state.to_vec()],
+            Instruction::Mov(r1, r2) if post(r1) == post(r2) && r2.as_index() + 1 == post_state.len() => {
+                vec![post_state[..r2.as_index()].to_vec()]
+            }
+            Instruction::Mov(..) => vec![],
+            Instruction::Add(r1, r2) if r1 == r2 => {
+                if post(r2) % 2 == 0 {
+                    vec![with(post(r2) / 2)]
+                } else {
+                    vec![]
+                }
+            }
+            Instruction::Add(r1, r2) => post(r2).checked_sub(post(r1)).map(with).into_iter().collect(),
+            Instruction::Mul(r1, r2) if r1 == r2 => {
+                // The float estimate can be one off for large squares
+                let estimate = (post(r2).max(0) as f64).sqrt() as Value;
+                match (estimate.max(1) - 1..=estimate + 1).find(|&root| root.checked_mul(root) == Some(post(r2))) {
+                    Some(0) => vec![with(0)],
+                    Some(root) => vec![with(-root), with(root)],
+                    None => vec![],
+                }
+            }
+            Instruction::Mul(r1, r2) => match post(r2).checked_rem(post(r1)) {
+                Some(0) => vec![with(post(r2) / post(r1))],
+                _ => vec![],
+            },
+            Instruction::Neg(r) => post(r).checked_neg().map(with).into_iter().collect(),
+            Instruction::Fused(..) => vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Program<'a> {
+    pub parent: Option<&'a Execution<'a>>,
+    pub instruction: Option<Instruction>,
+    // Instructions from the root, so 0 for the root itself
+    pub depth: usize,
+}
+
+#[derive(Debug)]
+pub struct Execution<'a> {
+    pub program: Program<'a>,
+    pub output: Vec<Vec<Value>>,
+    // Registers the program has written, in order of first write; see
+    // compute_ordering
+    pub ordering: Vec<RegisterIndex>,
+    // Bounds on each register over every input in the spec's domains,
+    // carried down from the root; empty when there are no domains
+    pub intervals: Vec<Interval>,
+}
+
+impl<'a> Execution<'a> {
+    pub fn root(inputs: Vec<Vec<Value>>) -> Execution<'a> {
+        Execution {
+            program: Program {
+                parent: None,
+                instruction: None,
+                depth: 0,
+            },
+            output: inputs,
+            ordering: vec![],
+            intervals: vec![],
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.program.depth
+    }
+
+    pub fn inputs(&self) -> &Vec<Vec<Value>> {
+        self.program.parent.map_or(&self.output, |p| p.inputs())
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.inputs()[0].len()
+    }
+
+    // Whether the last instruction left every register as it was on every
+    // test case, like `mov r0 r0`. The root has no last instruction and is
+    // never trivial.
+    pub fn is_trivial(&self) -> bool {
+        self.program.is_noop()
+    }
+
+    pub fn instructions(&self) -> InstructionIter<'_> {
+        InstructionIter {
+            current: Some(self),
+            pending: Vec::new(),
+        }
+    }
+
+    // Instructions from the root down to this execution
+    pub fn linearize(&self) -> Vec<Instruction> {
+        self.instructions().cloned().collect()
+    }
+
+    // `inst` run on this execution, allocated in `arena` as a generation of
+    // its own, so chains can be built without spelling out Programs:
+    //
+    //     let e1 = root.derive(Instruction::Add(r0, r1), &arena);
+    //     let e2 = e1.derive(Instruction::Neg(r1), &arena);
+    pub fn derive(&'a self, inst: Instruction, arena: &'a ExecutionArena<'a>) -> &'a Execution<'a> {
+        assert!(inst.is_valid_for(self.output[0].len()), "{:?} uses a register that does not exist yet", inst);
+        let child = execute(Program {
+            parent: Some(self),
+            instruction: Some(inst),
+            depth: self.depth() + 1,
+        });
+        arena.alloc_generation(std::iter::once(child).collect()).iter().next().unwrap()
+    }
+
+    // The values `reg` holds across the test cases, whichever case each
+    // comes from
+    pub fn reachable_outputs(&self, reg: RegisterIndex) -> HashSet<Value> {
+        self.output.iter().map(|row| row[reg.as_index()]).collect()
+    }
+}
+
+// The instructions of an execution's chain, root first. The chain only
+// links leaf to root, so the first call to next walks all of it.
+pub struct InstructionIter<'a> {
+    current: Option<&'a Execution<'a>>,
+    // Deepest first, so pop gives the next instruction
+    pending: Vec<&'a Instruction>,
+}
+
+impl<'a> Iterator for InstructionIter<'a> {
+    type Item = &'a Instruction;
+
+    fn next(&mut self) -> Option<&'a Instruction> {
+        if let Some(exe) = self.current.take() {
+            self.pending.reserve(exe.depth());
+            let mut node = Some(exe);
+            while let Some(exe) = node {
+                self.pending.extend(exe.program.instruction.as_ref());
+                node = exe.program.parent;
+            }
+        }
+        self.pending.pop()
+    }
+}
+
+impl<'a> std::cmp::PartialEq for Execution<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.output == other.output
+    }
+}
+
+impl<'a> std::cmp::Eq for Execution<'a> {}
+
+impl<'a> std::hash::Hash for Execution<'a> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.output.hash(state)
+    }
+}
+
+impl<'a> std::fmt::Display for Execution<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for inst in self.instructions() {
+            inst.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Instruction::Mov(r1, r2) => write!(f, "\nmov r{} r{}", r1, r2),
+            Instruction::Add(r1, r2) => write!(f, "\nadd r{} r{}", r1, r2),
+            Instruction::Mul(r1, r2) => write!(f, "\nmul r{} r{}", r1, r2),
+            //Instruction::Sub(r1, r2) => write!(f, "\nsub r{} r{}", r1, r2),
+            Instruction::Neg(r) => write!(f, "\nneg r{}", r),
+            Instruction::Fused(ref fused) => fused.0.iter().try_for_each(|inst| inst.fmt(f)),
+        }
+    }
+}
+
+// An execution that owns its whole program, so it can outlive the arena
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct OwnedExecution {
+    pub inputs: Vec<Vec<Value>>,
+    pub instructions: Vec<Instruction>,
+    pub output: Vec<Vec<Value>>,
+}
+
+impl OwnedExecution {
+    pub fn replay(inputs: Vec<Vec<Value>>, instructions: Vec<Instruction>) -> OwnedExecution {
+        let mut output = inputs.clone();
+        for testcase in &mut output {
+            for inst in &instructions {
+                inst.apply(testcase);
+            }
+        }
+
+        OwnedExecution {
+            inputs,
+            instructions,
+            output,
+        }
+    }
+
+    // Like exhaustive::verify_counterexample, for a program out of the arena
+    pub fn evaluate(&self, output_register: RegisterIndex, inputs: &[Value]) -> Value {
+        assert_eq!(inputs.len(), self.inputs[0].len(), "the program was synthesized for a different number of inputs");
+        exhaustive::run_on(&self.instructions, inputs)[output_register.as_index()]
+    }
+
+    // Like replay, but refuses programs that read registers not yet created
+    pub fn try_replay(inputs: Vec<Vec<Value>>, instructions: Vec<Instruction>) -> Option<OwnedExecution> {
+        let mut register_count = inputs[0].len();
+        for inst in &instructions {
+            if !inst.is_valid_for(register_count) {
+                return None;
+            }
+            register_count = inst.register_count_after(register_count);
+        }
+
+        Some(OwnedExecution::replay(inputs, instructions))
+    }
+}
+
+impl<'a, 'b> From<&'b Execution<'a>> for OwnedExecution {
+    fn from(exe: &'b Execution<'a>) -> OwnedExecution {
+        OwnedExecution {
+            inputs: exe.inputs().clone(),
+            instructions: exe.linearize(),
+            output: exe.output.clone(),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b Execution<'a>> for Vec<Instruction> {
+    fn from(exe: &'b Execution<'a>) -> Vec<Instruction> {
+        exe.linearize()
+    }
+}
+
+impl<'a, 'b> From<&'b Execution<'a>> for OutputMatrix {
+    fn from(exe: &'b Execution<'a>) -> OutputMatrix {
+        exe.output.clone()
+    }
+}
+
+// The program's depth
+impl<'a, 'b> From<&'b Execution<'a>> for usize {
+    fn from(exe: &'b Execution<'a>) -> usize {
+        exe.depth()
+    }
+}
+
+// Executions borrow from the arena, so they are saved in the owned form and
+// come back as OwnedExecution
+#[cfg(feature = "serialization")]
+impl<'a> Serialize for Execution<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        OwnedExecution::from(self).serialize(serializer)
+    }
+}
+
+impl std::cmp::PartialEq for OwnedExecution {
+    fn eq(&self, other: &Self) -> bool {
+        self.output == other.output
+    }
+}
+
+impl std::cmp::Eq for OwnedExecution {}
+
+impl std::hash::Hash for OwnedExecution {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.output.hash(state)
+    }
+}
+
+impl std::fmt::Display for OwnedExecution {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for inst in &self.instructions {
+            inst.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Program<'a> {
+    // Whether the instruction is an identity on every one of the parent's
+    // test cases, so executing it would reproduce the parent
+    pub fn is_noop(&self) -> bool {
+        match (self.parent, self.instruction.as_ref()) {
+            (Some(parent), Some(inst)) => parent.output.iter().all(|state| inst.is_identity(state)),
+            _ => false,
+        }
+    }
+
+    // Like is_noop, with arithmetic following `policy`
+    pub fn is_noop_with(&self, policy: OverflowPolicy) -> bool {
+        match (self.parent, self.instruction.as_ref()) {
+            (Some(parent), Some(inst)) => parent.output.iter().all(|state| inst.is_identity_with(state, policy)),
+            _ => false,
+        }
+    }
+}
+
+// Whether the last instructions of `program`, following its parents, are
+// exactly `pattern`
+pub fn check_pattern(program: &Program, pattern: &[Instruction]) -> bool {
+    let mut current = Some(program);
+
+    for expected in pattern.iter().rev() {
+        match current {
+            Some(&Program { instruction: Some(ref inst), parent, .. }) if inst == expected => {
+                current = parent.map(|p| &p.program);
+            }
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+// False for the mirror image of a commutative instruction, Add or Mul with
+// the higher register first. The two orders leave the result in different
+// registers, so skipping one can lengthen the shortest program by a mov.
+pub fn is_canonical_ordering(inst: &Instruction) -> bool {
+    match *inst {
+        Instruction::Add(r1, r2) | Instruction::Mul(r1, r2) => r1 <= r2,
+        Instruction::Mov(..) | Instruction::Neg(..) | Instruction::Fused(..) => true,
+    }
+}
+
+// Whether `program` ends in a mov copying back the register its parent's
+// mov just copied, as in `mov r0 r1; mov r1 r0`. The second mov never
+// changes anything.
+pub fn is_mov_round_trip(program: &Program) -> bool {
+    match (program.instruction.as_ref(), program.parent.and_then(|p| p.program.instruction.as_ref())) {
+        (Some(&Instruction::Mov(a, b)), Some(&Instruction::Mov(src, dst))) => a == dst && b == src,
+        _ => false,
+    }
+}
+
+pub fn add_one_instruction<'a>(parent: &'a Execution) -> Vec<Program<'a>> {
+    let parent_register_count = parent.output[0].len();
+    let depth = parent.program.depth + 1;
+
+    let mut new_programs = Vec::with_capacity(SynthesizerBuilder::instruction_count_bound(parent_register_count));
+
+    // One past the parent's last register; at MAX_REGISTERS there is none
+    let new_register = Register::new(0).checked_add(parent_register_count);
+
+    // Copy to new register
+    if let Some(new_register) = new_register {
+        for index in (0..parent_register_count).map(Register::new) {
+            new_programs.push(Program {
+                parent: Some(parent),
+                instruction: Some(Instruction::Mov(index, new_register)),
+                depth,
+            });
+        }
+    }
+
+    // Ops of two existing registers
+    for index in (0..parent_register_count).map(Register::new) {
+        for index2 in (0..parent_register_count).map(Register::new) {
+            new_programs.push(Program {
+                parent: Some(parent),
+                instruction: Some(Instruction::Mov(index, index2)),
+                depth,
+            });
+            new_programs.push(Program {
+                parent: Some(parent),
+                instruction: Some(Instruction::Add(index, index2)),
+                depth,
+            });
+            new_programs.push(Program {
+                parent: Some(parent),
+                instruction: Some(Instruction::Mul(index, index2)),
+                depth,
+            });
+            /*new_programs.push(Program {
+                parent: Some(parent),
+                instruction: Some(Instruction::Sub(index, index2)),
+                depth,
+            });*/
+        }
+        new_programs.push(Program {
+            parent: Some(parent),
+            instruction: Some(Instruction::Neg(index)),
+            depth,
+        })
+    }
+
+    debug_assert_eq!(new_programs.len(), SynthesizerBuilder::instruction_count_bound(parent_register_count));
+    // Nothing past the register a mov may create
+    debug_assert!(new_programs
+        .iter()
+        .filter_map(|program| program.instruction.as_ref())
+        .all(|inst| inst.max_register().as_index() <= parent_register_count));
+    new_programs
+}
+
+// Programs one generation of expansion would produce from `frontier`,
+// generated but never executed, to time instruction generation by itself
+pub fn dry_run_generation(frontier: &IndexSet<Execution>) -> usize {
+    par_frontier(frontier).map(|exe| add_one_instruction(exe).len()).sum()
+}
+
+// A frontier's executions in parallel, in insertion order. This rayon has
+// no parallel iterator for IndexSet, so it goes through the indices.
+pub fn par_frontier<'a, 'b>(
+    frontier: &'b IndexSet<Execution<'a>>,
+) -> impl IndexedParallelIterator<Item = &'b Execution<'a>> + 'b {
+    (0..frontier.len()).into_par_iter().map(move |index| &frontier[index])
+}
+
+// A generation in the order its executions were produced, keeping the
+// first of each set of equal ones, so the same parents always give the
+// same frontier however rayon splits the work
+pub fn collect_ordered<'a>(executions: impl ParallelIterator<Item = Execution<'a>>) -> IndexSet<Execution<'a>> {
+    executions.collect::<Vec<_>>().into_iter().collect()
+}
+
+// Deduplicate a generation the way rayon splits it: each piece folds into
+// a set of its own, and neighbouring sets are unioned pairwise instead of
+// merged by one thread at the end. Each union keeps the earlier piece's
+// order, so the result matches collect_ordered. The extra unions cost
+// more than they save on small machines.
+pub fn collect_frontier<'a>(executions: impl ParallelIterator<Item = Execution<'a>>) -> IndexSet<Execution<'a>> {
+    executions
+        .fold(IndexSet::new, |mut set, exe| {
+            set.insert(exe);
+            set
+        })
+        .reduce(IndexSet::new, |mut earlier, later| {
+            earlier.extend(later);
+            earlier
+        })
+}
+
+// Same programs as add_one_instruction, in an order fixed by `seed` and the
+// parent's output
+pub fn add_one_instruction_shuffled<'a>(parent: &'a Execution, seed: u64) -> Vec<Program<'a>> {
+    let mut hasher = DefaultHasher::new();
+    parent.hash(&mut hasher);
+    let mut rng = SmallRng::seed_from_u64(seed ^ hasher.finish());
+
+    let mut new_programs = add_one_instruction(parent);
+    new_programs.shuffle(&mut rng);
+    new_programs
+}
+
+pub fn execute(program: Program) -> Execution {
+    execute_with(program, OverflowPolicy::Wrapping).expect("wrapping arithmetic never overflows")
+}
+
+// Like execute, with arithmetic following `policy`. None if the policy is
+// Checked and the instruction overflows on some test case.
+pub fn execute_with(program: Program, policy: OverflowPolicy) -> Option<Execution> {
+    debug_assert_eq!(program.depth, program.parent.map_or(0, |p| p.program.depth + 1));
+    let mut all_testcases = program.parent.unwrap().output.clone();
+
+    for testcase in &mut all_testcases {
+        let applied = match program.instruction {
+            None => unreachable!(),
+            Some(ref inst) => inst.apply_with(testcase, policy),
+        };
+        if !applied {
+            return None;
+        }
+    }
+
+    let parent = program.parent.unwrap();
+    let mut ordering = parent.ordering.clone();
+    let mut intervals = vec![];
+    if let Some(ref inst) = program.instruction {
+        inst.push_writes(&mut ordering);
+        intervals = interval::step(inst, &parent.intervals);
+    }
+
+    let exe = Execution {
+        program,
+        output: all_testcases,
+        ordering,
+        intervals,
+    };
+    if cfg!(debug_assertions) {
+        if let Err(e) = validate_program(&exe) {
+            search_log!(error, "malformed program: {}", e);
+            panic!("{:?}", e);
+        }
+    }
+    Some(exe)
+}
+
+// What arithmetic does when the result does not fit in a Value. apply and
+// execute always wrap; the others are for SynthesizerBuilder::
+// with_overflow_policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum OverflowPolicy {
+    #[default]
+    Wrapping,
+    // Clamp to Value::MIN or Value::MAX
+    Saturating,
+    // A program that overflows on any test case is invalid
+    Checked,
+    // Plain `+`, `*` and unary `-`, so whatever the build's overflow checks
+    // say: a panic at the first overflow in a debug build, wrapping in a
+    // release build without overflow-checks. A search explores programs
+    // that overflow almost at once, so this is only sound for specs whose
+    // every candidate program stays in range, and a result found in one
+    // profile may not reproduce in the other. It is never the `unsafe`
+    // unchecked arithmetic, which would make each such program undefined
+    // behaviour.
+    Undefined,
+}
+
+impl OverflowPolicy {
+    fn add(self, a: Value, b: Value) -> Option<Value> {
+        match self {
+            OverflowPolicy::Wrapping => Some(a.wrapping_add(b)),
+            OverflowPolicy::Saturating => Some(a.saturating_add(b)),
+            OverflowPolicy::Checked => a.checked_add(b),
+            OverflowPolicy::Undefined => Some(a + b),
+        }
+    }
+
+    fn mul(self, a: Value, b: Value) -> Option<Value> {
+        match self {
+            OverflowPolicy::Wrapping => Some(a.wrapping_mul(b)),
+            OverflowPolicy::Saturating => Some(a.saturating_mul(b)),
+            OverflowPolicy::Checked => a.checked_mul(b),
+            OverflowPolicy::Undefined => Some(a * b),
+        }
+    }
+
+    fn neg(self, a: Value) -> Option<Value> {
+        match self {
+            OverflowPolicy::Wrapping => Some(a.wrapping_neg()),
+            OverflowPolicy::Saturating => Some(a.saturating_neg()),
+            OverflowPolicy::Checked => a.checked_neg(),
+            OverflowPolicy::Undefined => Some(-a),
+        }
+    }
+}
+
+// Which registers may hold a program's result. Plain verify is AnyRegister.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum VerificationPolicy {
+    #[default]
+    AnyRegister,
+    FixedRegister(usize),
+    // The highest-indexed register the program has
+    LastRegister,
+}
+
+impl VerificationPolicy {
+    pub fn verify_output(&self, output: &[Vec<Value>], tests: &[Value]) -> Option<RegisterIndex> {
+        self.verify_with(output, |register| register_matches(output, register, tests))
+    }
+
+    // Like verify_output, passing a register by weight as weighted_verify
+    // does
+    pub fn verify_weighted(
+        &self,
+        output: &[Vec<Value>],
+        tests: &[Value],
+        weights: &[f64],
+        threshold: f64,
+    ) -> Option<RegisterIndex> {
+        self.verify_with(output, |register| passing_weight(output, register, tests, weights) >= threshold)
+    }
+
+    // The first register the policy allows that `passes`
+    pub(crate) fn verify_with(
+        &self,
+        output: &[Vec<Value>],
+        passes: impl Fn(RegisterIndex) -> bool,
+    ) -> Option<RegisterIndex> {
+        let register = match *self {
+            VerificationPolicy::AnyRegister => return (0..output[0].len()).map(Register::new).find(|&reg| passes(reg)),
+            VerificationPolicy::FixedRegister(index) if index < output[0].len() => Register::new(index),
+            VerificationPolicy::FixedRegister(_) => return None,
+            VerificationPolicy::LastRegister => Register::new(output[0].len() - 1),
+        };
+        Some(register).filter(|&register| passes(register))
+    }
+}
+
+// Each register `instructions` write, once, in the order they are first
+// written. Registers only read, like inputs left alone, do not appear.
+pub fn compute_ordering(instructions: &[Instruction]) -> Vec<RegisterIndex> {
+    let mut ordering = Vec::new();
+    for inst in instructions {
+        inst.push_writes(&mut ordering);
+    }
+    ordering
+}
+
+pub fn verify(exe: &Execution, tests: &[Value]) -> Option<RegisterIndex> {
+    verify_output(&exe.output, tests)
+}
+
+// Register counts above which verify_par checks registers in parallel.
+// Only measured on one core, where the parallel check always loses: about
+// 7-10us against 20-300ns for verify on 16 test cases, up to 256
+// registers. Worth recalibrating with real cores and many test cases.
+pub const VERIFY_PAR_THRESHOLD: usize = 8;
+
+// Like verify, checking the registers in parallel once there are more than
+// VERIFY_PAR_THRESHOLD of them. Returns the same register as verify.
+pub fn verify_par(exe: &Execution, tests: &[Value]) -> Option<RegisterIndex> {
+    let register_count = exe.output[0].len();
+    if register_count <= VERIFY_PAR_THRESHOLD {
+        return verify(exe, tests);
+    }
+
+    (0..register_count)
+        .into_par_iter()
+        .map(Register::new)
+        .find_first(|&output_register| register_matches(&exe.output, output_register, tests))
+}
+
+pub fn verify_output(output: &[Vec<Value>], tests: &[Value]) -> Option<RegisterIndex> {
+    let register_count = output[0].len();
+
+    (0..register_count)
+        .map(Register::new)
+        .find(|&output_register| register_matches(output, output_register, tests))
+}
+
+// The first register whose passing test cases carry at least `threshold`
+// of the total weight, so with a threshold of 0.95 a register may miss
+// cases worth up to 5% of it. `weights` has one entry per test case.
+pub fn weighted_verify(exe: &Execution, outputs: &[Value], weights: &[f64], threshold: f64) -> Option<RegisterIndex> {
+    weighted_verify_output(&exe.output, outputs, weights, threshold)
+}
+
+pub fn weighted_verify_output(
+    output: &[Vec<Value>],
+    tests: &[Value],
+    weights: &[f64],
+    threshold: f64,
+) -> Option<RegisterIndex> {
+    (0..output[0].len())
+        .map(Register::new)
+        .find(|&register| passing_weight(output, register, tests, weights) >= threshold)
+}
+
+// The share of the total weight on the test cases where `register` holds
+// the target
+pub(crate) fn passing_weight(output: &[Vec<Value>], register: RegisterIndex, tests: &[Value], weights: &[f64]) -> f64 {
+    assert_eq!(weights.len(), tests.len(), "one weight per test case");
+    let passing = output
+        .iter()
+        .zip(tests)
+        .zip(weights)
+        .filter(|&((row, test), _)| row[register.as_index()] == *test)
+        .map(|(_, &weight)| weight)
+        .sum::<f64>();
+    passing / weights.iter().sum::<f64>()
+}
+
+// Every register holding the target on all test cases, not just the first
+pub fn verify_all_registers(exe: &Execution, tests: &[Value]) -> Vec<RegisterIndex> {
+    let register_count = exe.output[0].len();
+
+    (0..register_count)
+        .map(Register::new)
+        .filter(|&output_register| register_matches(&exe.output, output_register, tests))
+        .collect()
+}
+
+pub(crate) fn register_matches(output: &[Vec<Value>], register: RegisterIndex, tests: &[Value]) -> bool {
+    output
+        .iter()
+        .zip(tests.iter())
+        .all(|(output, test_value)| output[register.as_index()] == *test_value)
+}
+
+// Like register_matches, comparing the test cases in parallel
+pub(crate) fn register_matches_par(output: &[Vec<Value>], register: RegisterIndex, tests: &[Value]) -> bool {
+    output
+        .par_iter()
+        .zip(tests.par_iter())
+        .all(|(output, test_value)| output[register.as_index()] == *test_value)
+}
+
+// Negated L1 distance between the best register and the targets, so 0.0
+// means solved and higher is better
+pub fn score(output: &[Vec<Value>], tests: &[Value]) -> f64 {
+    let register_count = output[0].len();
+
+    (0..register_count)
+        .map(|register| {
+            -output
+                .iter()
+                .zip(tests.iter())
+                .map(|(row, test_value)| (row[register] as f64 - *test_value as f64).abs())
+                .sum::<f64>()
+        })
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+#[macro_export]
+macro_rules! testcases [
+    ( $( ([ $($input: expr),* ], $output:expr), )* ) => {
+        $crate::TestSuite::new(vec![
+            $( $crate::TestCase::new(vec![$($input),*], $output), )*
+        ])
+    };
+    ( $( ([ $($input: expr),* ], $output:expr), )* where $( $name:ident in [$lo:expr, $hi:expr] ),* ) => {
+        $crate::TestSuite::new(vec![
+            $( $crate::TestCase::new(vec![$($input),*], $output), )*
+        ])
+        $( .with_named_domain(stringify!($name), $lo, $hi) )*
+    };
+];
+
+// Like testcases!, but with named inputs:
+//
+//     named_testcases![(x = 0, y = 1) => 1, (x = 1, y = 0) => -1]
+//
+// gives the TestSuite and the input names, for pretty_print. Every case must
+// name the same inputs in the same order.
+#[macro_export]
+macro_rules! named_testcases [
+    ( $( ( $($name:ident = $input:expr),* ) => $output:expr ),* $(,)* ) => {
+        $crate::testsuite::named_suite(vec![
+            $( (vec![$(stringify!($name)),*], $crate::TestCase::new(vec![$($input),*], $output)), )*
+        ])
+    };
+];
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use {execute_with, Execution, ExecutionArena, Instruction, OverflowPolicy, Program, Register, Value};
+    use {verify, verify_par, weighted_verify, SynthesizerBuilder, TestCase, TestSuite, VERIFY_PAR_THRESHOLD};
+
+    fn negative_double() -> TestSuite {
+        TestSuite::new(vec![TestCase::new(vec![Value::MAX], -2), TestCase::new(vec![1], 2)])
+    }
+
+    // add r0 r0 matches MAX -> -2 only by wrapping, which Checked rejects
+    #[test]
+    fn checked_policy_filters_overflow() {
+        let arena = ExecutionArena::new();
+        let root = arena.alloc_generation(iter::once(Execution::root(vec![vec![Value::MAX], vec![1]])).collect());
+        let r0 = Register::new(0);
+        let double = |policy| {
+            let program = Program {
+                parent: root.iter().next(),
+                instruction: Some(Instruction::Add(r0, r0)),
+                depth: 1,
+            };
+            execute_with(program, policy).map(|exe| exe.output)
+        };
+        assert_eq!(double(OverflowPolicy::Wrapping), Some(vec![vec![-2], vec![2]]));
+        assert_eq!(double(OverflowPolicy::Saturating), Some(vec![vec![Value::MAX], vec![2]]));
+        assert_eq!(double(OverflowPolicy::Checked), None);
+
+        let wrapping = SynthesizerBuilder::new().build(&negative_double()).find_all_at_depth(1);
+        assert!(wrapping.iter().any(|(exe, _)| exe.instructions == vec![Instruction::Add(r0, r0)]));
+        let checked = SynthesizerBuilder::new().with_overflow_policy(OverflowPolicy::Checked);
+        assert!(checked.build(&negative_double()).find_all_at_depth(1).is_empty());
+    }
+
+    // Registers 10 and 11 both hold the targets; verify_par must still pick
+    // the first, as verify does
+    #[test]
+    fn verify_par_matches_verify_above_threshold() {
+        let mut rows = vec![(0..12).collect::<Vec<Value>>(), (100..112).collect()];
+        rows[0][11] = 10;
+        rows[1][11] = 110;
+        assert!(rows[0].len() > VERIFY_PAR_THRESHOLD);
+        let exe = Execution::root(rows);
+
+        assert_eq!(verify(&exe, &[10, 110]), Some(Register::new(10)));
+        assert_eq!(verify_par(&exe, &[10, 110]), Some(Register::new(10)));
+        assert_eq!(verify_par(&exe, &[11, 100]), None);
+        assert_eq!(verify(&exe, &[11, 100]), None);
+    }
+
+    // r0 passes cases worth 4 of the 8, r1 cases worth 6
+    #[test]
+    fn weighted_verify_thresholds() {
+        let exe = Execution::root(vec![vec![1, 0], vec![2, 2], vec![0, 3], vec![0, 4]]);
+        let (tests, weights) = ([1, 2, 3, 4], [2.0, 2.0, 1.0, 3.0]);
+
+        assert_eq!(weighted_verify(&exe, &tests, &weights, 0.5), Some(Register::new(0)));
+        assert_eq!(weighted_verify(&exe, &tests, &weights, 0.6), Some(Register::new(1)));
+        assert_eq!(weighted_verify(&exe, &tests, &weights, 0.75), Some(Register::new(1)));
+        assert_eq!(weighted_verify(&exe, &tests, &weights, 0.8), None);
+    }
+}
@@ -1,180 +1,209 @@
-#![feature(conservative_impl_trait)]
+#[macro_use]
+extern crate vasm_sim;
+use vasm_sim::{batch_synthesize, instruction_histogram, OwnedExecution, RegisterIndex, SynthesizerBuilder, TestCase, TestSuite};
+use vasm_sim::{verify_is_minimal, AsmTarget, OutputFormat, ProgramPrinter, Synthesizer, Value};
+
+#[cfg(feature = "log")]
+extern crate env_logger;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::process;
+
+#[cfg(any(feature = "config", feature = "cache"))]
+use std::path::Path;
+#[cfg(feature = "cache")]
+use vasm_sim::SynthesisCache;
+#[cfg(feature = "config")]
+use vasm_sim::SynthesizerConfig;
+
+struct Problem {
+    name: String,
+    suite: TestSuite,
+}
+
+// One test case per line: `name,input0,input1,...,output`. Lines sharing a
+// name make up one problem; blank lines and `#` comments are skipped.
+fn parse_problems(text: &str) -> Result<Vec<Problem>, String> {
+    let mut problems: Vec<Problem> = Vec::new();
 
-extern crate typed_arena;
-use typed_arena::Arena;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
 
-extern crate rayon;
-use rayon::prelude::*;
+        let mut fields = line.split(',').map(str::trim);
+        let name = fields.next().unwrap().to_string();
+        let values = fields
+            .map(|field| field.parse::<Value>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        let (output, inputs) = values
+            .split_last()
+            .ok_or_else(|| format!("line {}: expected inputs and an output", number + 1))?;
+
+        let case = TestCase::new(inputs.to_vec(), *output);
+        match problems.iter_mut().find(|problem| problem.name == name) {
+            Some(problem) => problem.suite.push(case),
+            None => problems.push(Problem {
+                name,
+                suite: TestSuite::new(vec![case]),
+            }),
+        }
+    }
 
-use std::collections::HashSet;
+    Ok(problems)
+}
 
-type RegisterIndex = usize;
-type Value = isize;
+fn load_problems(path: &str) -> Result<Vec<Problem>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_problems(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+#[cfg(feature = "cache")]
+type Cache = SynthesisCache;
+
+#[cfg(not(feature = "cache"))]
+enum Cache {}
+
+#[cfg(feature = "cache")]
+fn open_cache(path: &str) -> Result<Cache, String> {
+    SynthesisCache::new(Path::new(path).to_path_buf()).map_err(|e| format!("{}: {}", path, e))
+}
 
-#[derive(Debug)]
-enum Instruction {
-    Mov(RegisterIndex, RegisterIndex),
-    Add(RegisterIndex, RegisterIndex),
-    Mul(RegisterIndex, RegisterIndex),
-    //Sub(RegisterIndex, RegisterIndex),
-    Neg(RegisterIndex),
+#[cfg(not(feature = "cache"))]
+fn open_cache(_path: &str) -> Result<Cache, String> {
+    Err("--cache needs the cache feature".to_string())
 }
 
-#[derive(Debug)]
-struct Program<'a> {
-    parent: Option<&'a Execution<'a>>,
-    instruction: Option<Instruction>,
+#[cfg(feature = "cache")]
+fn cached(cache: Option<&Cache>, spec: &TestSuite) -> Option<(OwnedExecution, RegisterIndex)> {
+    cache?.lookup(spec).map(|sol| (sol.execution, sol.register))
 }
 
-#[derive(Debug)]
-struct Execution<'a> {
-    program: Program<'a>,
-    output: Vec<Vec<Value>>,
-    ordering: Vec<RegisterIndex>,
+#[cfg(not(feature = "cache"))]
+fn cached(_cache: Option<&Cache>, _spec: &TestSuite) -> Option<(OwnedExecution, RegisterIndex)> {
+    None
 }
 
-impl<'a> std::cmp::PartialEq for Execution<'a> {
-    fn eq(&self, other: &Self) -> bool {
-        self.output == other.output
+#[cfg(feature = "cache")]
+fn remember(cache: Option<&Cache>, spec: &TestSuite, exe: &OwnedExecution, reg: RegisterIndex) -> Result<(), String> {
+    match cache {
+        Some(cache) => cache.store(spec, exe, reg).map_err(|e| e.to_string()),
+        None => Ok(()),
     }
 }
 
-impl<'a> std::cmp::Eq for Execution<'a> {}
+#[cfg(not(feature = "cache"))]
+fn remember(_cache: Option<&Cache>, _spec: &TestSuite, _exe: &OwnedExecution, _reg: RegisterIndex) -> Result<(), String> {
+    Ok(())
+}
 
-impl<'a> std::hash::Hash for Execution<'a> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.output.hash(state)
+fn check_minimal(name: &str, spec: &TestSuite, exe: &OwnedExecution, builder: &SynthesizerBuilder) {
+    if verify_is_minimal(spec, exe.instructions.len(), builder) {
+        println!("{}: minimal", name);
+    } else {
+        println!("{}: a shorter program exists", name);
     }
 }
 
-impl<'a> std::fmt::Display for Execution<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        self.program.parent.map_or(Ok(()), |p| p.fmt(f))?;
-        match self.program.instruction {
-            Some(ref inst) => inst.fmt(f),
-            None => Ok(())
+// The solution after a `name:` line in `format`, or on the same line as
+// `status` as before --format
+fn print_solution(name: &str, status: &str, exe: &OwnedExecution, reg: RegisterIndex, format: Option<OutputFormat>) {
+    match format {
+        Some(format) => {
+            println!("{}: {}", name, status);
+            if let Err(e) = format.print_owned(exe, reg, &mut io::stdout().lock()) {
+                eprintln!("{}: {}", name, e);
+            }
         }
+        None => println!("{}: {}{}\noutput in r{}", name, status, exe, reg),
     }
 }
 
-impl std::fmt::Display for Instruction {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            &Instruction::Mov(r1, r2) => write!(f, "\nmov r{} r{}", r1, r2),
-            &Instruction::Add(r1, r2) => write!(f, "\nadd r{} r{}", r1, r2),
-            &Instruction::Mul(r1, r2) => write!(f, "\nmul r{} r{}", r1, r2),
-            //&Instruction::Sub(r1, r2) => write!(f, "\nsub r{} r{}", r1, r2),
-            &Instruction::Neg(r) => write!(f, "\nneg r{}", r),
+fn print_test_inputs(name: &str, exe: &OwnedExecution, reg: RegisterIndex, test_inputs: &[Vec<Value>]) {
+    for inputs in test_inputs {
+        if inputs.len() == exe.inputs[0].len() {
+            println!("{}: {:?} -> {}", name, inputs, exe.evaluate(reg, inputs));
+        } else {
+            println!("{}: {:?} has the wrong number of inputs", name, inputs);
         }
     }
 }
 
-fn add_one_instruction<'a>(parent: &'a Execution) -> Vec<Program<'a>> {
-    let parent_register_count = parent.output[0].len();
-
-    let mut new_programs = Vec::with_capacity(
-        parent_register_count * 2 + (parent_register_count * parent_register_count * 3),
-    );
-
-    // Copy to new register
-    for index in 0..parent_register_count {
-        new_programs.push(Program {
-            parent: Some(parent),
-            instruction: Some(Instruction::Mov(index, parent_register_count)),
-        });
-    }
-
-    // Ops of two existing registers
-    for index in 0..parent_register_count {
-        for index2 in 0..parent_register_count {
-            new_programs.push(Program {
-                parent: Some(parent),
-                instruction: Some(Instruction::Mov(index, index2)),
-            });
-            new_programs.push(Program {
-                parent: Some(parent),
-                instruction: Some(Instruction::Add(index, index2)),
-            });
-            new_programs.push(Program {
-                parent: Some(parent),
-                instruction: Some(Instruction::Mul(index, index2)),
-            });
-            /*new_programs.push(Program {
-                parent: Some(parent),
-                instruction: Some(Instruction::Sub(index, index2)),
-            });*/
+// Prints each solution in `format`, runs it on every `--test-input`, and
+// with `verify_minimal` searches again for anything shorter. Specs solved
+// in `cache` are not searched again, and new solutions are added to it.
+fn run_batch(
+    path: &str,
+    histogram: bool,
+    format: Option<OutputFormat>,
+    test_inputs: &[Vec<Value>],
+    verify_minimal: bool,
+    cache: Option<&Cache>,
+    builder: &SynthesizerBuilder,
+) -> Result<(), String> {
+    let problems = load_problems(path)?;
+
+    for problem in &problems {
+        if let Err(warnings) = builder.build(&problem.suite).validate_spec() {
+            for warning in warnings {
+                eprintln!("{}: warning: {}", problem.name, warning);
+            }
         }
-        new_programs.push(Program {
-            parent: Some(parent),
-            instruction: Some(Instruction::Neg(index)),
-        })
-    }
-
-    debug_assert!(new_programs.len() == new_programs.capacity());
-    new_programs
-}
-
-fn execute<'a>(program: Program<'a>) -> Execution<'a> {
-    let mut all_testcases = program.parent.unwrap().output.clone();
-
-    for mut testcase in &mut all_testcases {
-        match program.instruction {
-            None => unreachable!(),
-            Some(Instruction::Mov(r1, r2)) => if testcase.len() <= r2 {
-                let temp = testcase[r1];
-                testcase.push(temp);
-            } else {
-                testcase[r2] = testcase[r1];
-            },
-            Some(Instruction::Add(r1, r2)) => testcase[r2] = testcase[r1].wrapping_add(testcase[r2]),
-            Some(Instruction::Mul(r1, r2)) => testcase[r2] = testcase[r1].wrapping_mul(testcase[r2]),
-            //Some(Instruction::Sub(r1, r2)) => testcase[r2] = testcase[r1].wrapping_add(-testcase[r2]),
-            Some(Instruction::Neg(r)) => testcase[r] = testcase[r].wrapping_mul(-1),
-        };
-    }
-
-    Execution {
-        program: program,
-        output: all_testcases,
-        ordering: vec![],
-    }
-}
-
-fn verify<'a>(exe: &'a Execution<'a>, tests: &Vec<isize>) -> Option<RegisterIndex> {
-    let register_count = exe.output[0].len();
-
-    (0..register_count).find(|output_register| {
-        exe.output
-            .iter()
-            .zip(tests.iter())
-            .all(|(output, test_value)| {
-                output[*output_register] == *test_value
-            })
-    })
-}
-
-macro_rules! testcases [
-    ( $( ([ $($input: expr),* ], $output:expr), )* ) => {
-        {
-        let mut inputs: Vec<Vec<Value>> = Vec::new();
-        let mut outputs: Vec<Value> = Vec::new();
-
-        $(
-            let mut inputrow = Vec::new();
-            $(
-                inputrow.push($input);
-            )*
-            inputs.push(inputrow);
-            outputs.push($output);
-        )*
-
-        (inputs, outputs)
+    }
+
+    let mut solutions = Vec::new();
+    let mut uncached = Vec::new();
+    for problem in problems {
+        match cached(cache, &problem.suite) {
+            Some((exe, reg)) => {
+                print_solution(&problem.name, "cached", &exe, reg, format);
+                print_test_inputs(&problem.name, &exe, reg, test_inputs);
+                if verify_minimal {
+                    check_minimal(&problem.name, &problem.suite, &exe, builder);
+                }
+                solutions.push(exe);
+            }
+            None => uncached.push(problem),
         }
     }
-];
 
-fn main() {
-    let (inputs, outputs) = testcases![
+    let specs = uncached.iter().map(|problem| problem.suite.clone()).collect();
+    for (index, result) in batch_synthesize(specs, builder) {
+        let problem = &uncached[index];
+        match result {
+            Ok(Some((exe, reg))) => {
+                print_solution(&problem.name, "found", &exe, reg, format);
+                print_test_inputs(&problem.name, &exe, reg, test_inputs);
+                if verify_minimal {
+                    check_minimal(&problem.name, &problem.suite, &exe, builder);
+                }
+                if let Err(e) = remember(cache, &problem.suite, &exe, reg) {
+                    eprintln!("{}: could not cache solution: {}", problem.name, e);
+                }
+                solutions.push(exe);
+            }
+            Ok(None) => println!("{}: no program found", problem.name),
+            Err(e) => println!("{}: {}", problem.name, e),
+        }
+    }
+
+    if histogram {
+        let counts = instruction_histogram(&solutions).into_iter().collect::<BTreeMap<_, _>>();
+        println!("instruction histogram:");
+        for (variant, count) in counts {
+            println!("{:?} {}", variant, count);
+        }
+    }
+
+    Ok(())
+}
+
+fn demo_spec() -> TestSuite {
+    testcases![
         ([0, 0], 0),
         ([0, 1], -1),
         ([1, 1], 0),
@@ -183,58 +212,183 @@ fn main() {
         ([-1, 0], -1),
         ([-1, -1], 0),
         ([1, -1], 2),
-        ([-1, 1], -2),       
-    ];
-
-    let mut starts = HashSet::default();
-    let start_exec = Execution {
-        program: Program {
-            parent: None,
-            instruction: None,
-        },
-        output: inputs,
-        ordering: vec![],
-    };
+        ([-1, 1], -2),
+    ]
+}
 
-    if verify(&start_exec, &outputs).is_some() {
-        println!("Get out.");
-        return;
+// The synthesizer prints the solution itself, as Text unless `format` says
+// otherwise
+fn run_demo(format: Option<OutputFormat>, builder: &SynthesizerBuilder) {
+    let spec = demo_spec();
+    let synthesizer = builder
+        .clone()
+        .with_output_format_for_solution(format.unwrap_or(OutputFormat::Text))
+        .build(&spec);
+
+    match synthesizer.run() {
+        Ok(Some((ref exe, _))) if exe.instructions.is_empty() => println!("Get out."),
+        Ok(Some(_)) => {}
+        Ok(None) => println!("No program found"),
+        Err(e) => println!("{}", e),
     }
+}
 
-    starts.insert(start_exec);
-    let old_executions = Arena::new();
-    //let mut prev_generations = Vec::new();
-    let mut last_generation = Some(&*old_executions.alloc(starts));
-    let mut generation = 1;
+// Estimates instead of searching, for each problem in `path` or the demo
+fn run_estimates(path: Option<&str>, builder: &SynthesizerBuilder) -> Result<(), String> {
+    let problems = match path {
+        Some(path) => load_problems(path)?,
+        None => vec![Problem {
+            name: "demo".to_string(),
+            suite: demo_spec(),
+        }],
+    };
+    for problem in problems {
+        let estimate = Synthesizer::benchmark_spec(&problem.suite, builder);
+        match estimate.estimated_depth {
+            Some(depth) => println!("{}: depth {} ({:.0}% confidence)", problem.name, depth, estimate.confidence * 100.0),
+            None => println!("{}: depth unknown", problem.name),
+        }
+        println!("{}: {}", problem.name, estimate.reasoning);
+    }
+    Ok(())
+}
 
-    loop {
-        println!("{}", generation);
-        //prev_generations.push(last_generation.clone().unwrap());
+#[cfg(feature = "config")]
+fn load_config(path: &str) -> Result<SynthesizerBuilder, String> {
+    let config = SynthesizerConfig::from_toml(Path::new(path)).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(config.into_builder())
+}
 
-        let old_programs = last_generation.take().unwrap().into_par_iter();
+#[cfg(not(feature = "config"))]
+fn load_config(_path: &str) -> Result<SynthesizerBuilder, String> {
+    Err("--config needs the config feature".to_string())
+}
 
-        let new_programs = old_programs.flat_map(add_one_instruction);
+fn parse_format(name: &str) -> Option<OutputFormat> {
+    match name {
+        "text" => Some(OutputFormat::Text),
+        "json" => Some(OutputFormat::Json),
+        "x86-64" => Some(OutputFormat::Asm(AsmTarget::X86_64)),
+        "wat" => Some(OutputFormat::Wat),
+        "dot" => Some(OutputFormat::Dot),
+        _ => None,
+    }
+}
 
-        let new_executions = new_programs.map(execute);
+fn main() {
+    #[cfg(feature = "log")]
+    env_logger::init();
+
+    let mut histogram = false;
+    let mut format = None;
+    let mut estimate = false;
+    let mut verify_minimal = false;
+    let mut path = None;
+    let mut builder = SynthesizerBuilder::new();
+    let mut test_inputs = Vec::new();
+    let mut cache = None;
+    let mut max_generations = None;
+    let mut max_frontier = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--histogram" => histogram = true,
+            "--verify-minimal" => verify_minimal = true,
+            "--estimate" => estimate = true,
+            "--format" => {
+                let name = args.next().unwrap_or_default();
+                format = Some(parse_format(&name).unwrap_or_else(|| {
+                    eprintln!("--format needs one of text, json, x86-64, wat or dot");
+                    process::exit(2);
+                }));
+            }
+            "--test-input" => {
+                let inputs = args
+                    .next()
+                    .ok_or_else(|| "--test-input needs comma-separated values".to_string())
+                    .and_then(|values| {
+                        values
+                            .split(',')
+                            .map(|value| value.trim().parse::<Value>().map_err(|e| format!("--test-input: {}", e)))
+                            .collect::<Result<Vec<_>, _>>()
+                    });
+                match inputs {
+                    Ok(inputs) => test_inputs.push(inputs),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        process::exit(2);
+                    }
+                }
+            }
+            "--config" => {
+                let config = args.next().unwrap_or_else(|| {
+                    eprintln!("--config needs a path");
+                    process::exit(2);
+                });
+                builder = load_config(&config).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    process::exit(2);
+                });
+            }
+            "--max-generations" => {
+                let generations = args.next().and_then(|n| n.parse::<usize>().ok()).unwrap_or_else(|| {
+                    eprintln!("--max-generations needs a number");
+                    process::exit(2);
+                });
+                max_generations = Some(generations);
+            }
+            "--max-frontier" => {
+                let size = args.next().and_then(|n| n.parse::<usize>().ok()).unwrap_or_else(|| {
+                    eprintln!("--max-frontier needs a number");
+                    process::exit(2);
+                });
+                max_frontier = Some(size);
+            }
+            "--cache" => {
+                let dir = args.next().unwrap_or_else(|| {
+                    eprintln!("--cache needs a path");
+                    process::exit(2);
+                });
+                match open_cache(&dir) {
+                    Ok(opened) => cache = Some(opened),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        process::exit(2);
+                    }
+                }
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("unknown option {}", arg);
+                process::exit(2);
+            }
+            _ => path = Some(arg),
+        }
+    }
 
-        let filtered_executions = new_executions
-            // .filter(|newexe| {
-            //     prev_generations
-            //         .iter()
-            //         .all(|prevgen| !prevgen.contains(newexe))
-            // })
-            .collect::<HashSet<_>>();
+    // After the loop so --config cannot undo them
+    if let Some(generations) = max_generations {
+        builder = builder.with_generation_limit(generations);
+    }
+    if let Some(size) = max_frontier {
+        builder = builder.with_frontier_size_limit(size);
+    }
 
-        if let Some(exe) = filtered_executions
-            .par_iter()
-            .find_any(|exe| verify(&exe, &outputs).is_some())
-        {
-            println!("Found {}", exe);
-            println!("Output gets stored in r{}", "?"); // TODO
-            break;
+    if estimate {
+        if let Err(e) = run_estimates(path.as_deref(), &builder) {
+            eprintln!("{}", e);
+            process::exit(1);
         }
+        return;
+    }
 
-        last_generation = Some(old_executions.alloc(filtered_executions));
-        generation += 1;
+    match path {
+        Some(path) => {
+            if let Err(e) = run_batch(&path, histogram, format, &test_inputs, verify_minimal, cache.as_ref(), &builder) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        None => run_demo(format, &builder),
     }
 }
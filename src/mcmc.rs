@@ -0,0 +1,73 @@
+use rand::Rng;
+
+use mutation::{random_program, replace_one};
+use rng::{SearchRng, SharedRng};
+use strategy::{CancellationToken, SearchStrategy};
+use testsuite::TestSuite;
+use {score, verify_output};
+use {OwnedExecution, RegisterIndex};
+
+const INITIAL_TEMPERATURE: f64 = 10.0;
+const COOLING_RATE: f64 = 0.99;
+
+// Metropolis-Hastings over programs of a fixed length, after STOKE: start
+// from a random program, propose single-instruction replacements and
+// accept each proposed mutation with probability
+// min(1, exp(delta / temperature)), cooling after every accepted step
+#[derive(Debug, Clone)]
+pub struct MCMCSearchStrategy {
+    length: usize,
+    iterations: usize,
+    rng: Option<SharedRng>,
+}
+
+impl MCMCSearchStrategy {
+    pub fn new(length: usize, iterations: usize) -> MCMCSearchStrategy {
+        assert!(length > 0, "MCMC needs at least one instruction to mutate");
+        MCMCSearchStrategy {
+            length,
+            iterations,
+            rng: None,
+        }
+    }
+
+    pub fn with_rng(mut self, rng: SharedRng) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+}
+
+impl SearchStrategy for MCMCSearchStrategy {
+    fn run(
+        &self,
+        spec: &TestSuite,
+        cancel: CancellationToken,
+    ) -> Option<(OwnedExecution, RegisterIndex)> {
+        let rng = &mut SearchRng::from_config(&self.rng);
+        let outputs = &spec.outputs();
+        let mut current = random_program(spec.inputs(), self.length, rng);
+        let mut current_score = score(&current.output, outputs);
+        let mut temperature = INITIAL_TEMPERATURE;
+
+        for _ in 0..self.iterations {
+            if let Some(reg) = verify_output(&current.output, outputs) {
+                return Some((current, reg));
+            }
+            if cancel.is_cancelled() {
+                return None;
+            }
+
+            let proposal = replace_one(&current, rng);
+            let proposal_score = score(&proposal.output, outputs);
+            let delta = proposal_score - current_score;
+
+            if delta >= 0.0 || rng.gen::<f64>() < (delta / temperature).exp() {
+                current = proposal;
+                current_score = proposal_score;
+                temperature *= COOLING_RATE;
+            }
+        }
+
+        verify_output(&current.output, outputs).map(|reg| (current, reg))
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::iter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use equivalence::OutputMatrix;
+use interval;
+use {execute_with, Execution, Instruction, OverflowPolicy, Program, Value};
+
+// Locks the table is split across, so parallel workers rarely wait on
+// each other
+const SHARDS: usize = 64;
+
+type Shard = HashMap<OutputMatrix, HashMap<Instruction, Option<OutputMatrix>>>;
+
+fn output_hash(output: &[Vec<Value>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    output.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Outputs of instructions already run, keyed by the parent's whole output
+// matrix and the instruction, so a parent reached again by another chain
+// of instructions does not rerun them. Parents are spread over shards by
+// a hash of their output, and only one shard is locked at a time. Entries
+// hold None where the overflow policy rejected the instruction, so one
+// table should only be used with one policy.
+#[derive(Debug)]
+pub struct MemoTable {
+    shards: Vec<Mutex<Shard>>,
+    hits: AtomicUsize,
+}
+
+impl Default for MemoTable {
+    fn default() -> MemoTable {
+        MemoTable {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            hits: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl MemoTable {
+    pub fn new() -> MemoTable {
+        MemoTable::default()
+    }
+
+    fn shard(&self, output: &[Vec<Value>]) -> &Mutex<Shard> {
+        &self.shards[output_hash(output) as usize % SHARDS]
+    }
+
+    // Like execute_with, reusing the output of an earlier call with the same
+    // parent output and instruction
+    pub fn execute<'a>(&self, program: Program<'a>, policy: OverflowPolicy) -> Option<Execution<'a>> {
+        let parent = program.parent.unwrap();
+        let inst = program.instruction.clone().unwrap();
+        let shard = self.shard(&parent.output);
+
+        let cached = shard.lock().unwrap().get(&parent.output).and_then(|children| children.get(&inst).cloned());
+        if let Some(output) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            let output = output?;
+            let mut ordering = parent.ordering.clone();
+            inst.push_writes(&mut ordering);
+            return Some(Execution {
+                intervals: interval::step(&inst, &parent.intervals),
+                program,
+                output,
+                ordering,
+            });
+        }
+
+        let exe = execute_with(program, policy);
+        let output = exe.as_ref().map(|exe| exe.output.clone());
+        let mut shard = shard.lock().unwrap();
+        match shard.get_mut(&parent.output) {
+            Some(children) => {
+                children.insert(inst, output);
+            }
+            None => {
+                shard.insert(parent.output.clone(), iter::once((inst, output)).collect());
+            }
+        }
+        exe
+    }
+
+    // Calls to execute answered from the table
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    // Distinct parent outputs and instructions stored
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().values().map(HashMap::len).sum::<usize>())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoTable;
+    use arena::ExecutionArena;
+    use testsuite::{TestCase, TestSuite};
+    use {Execution, Instruction, OverflowPolicy, Program, Register, SynthesizerBuilder};
+
+    fn spec() -> TestSuite {
+        TestSuite::new(vec![
+            TestCase::new(vec![2, 3], 25),
+            TestCase::new(vec![-1, 4], 9),
+            TestCase::new(vec![0, 5], 25),
+        ])
+    }
+
+    // (x + y)^2 takes two instructions, so the memo is exercised on the
+    // way there
+    #[test]
+    fn memoized_search_matches_plain_search() {
+        for policy in [OverflowPolicy::Wrapping, OverflowPolicy::Checked] {
+            let plain = SynthesizerBuilder::new().with_overflow_policy(policy);
+            let memoized = plain.clone().with_execute_memo(true);
+
+            assert_eq!(plain.build(&spec()).run().unwrap(), memoized.build(&spec()).run().unwrap());
+            let instructions = |builder: &SynthesizerBuilder| {
+                let solutions = builder.build(&spec()).find_all_at_depth(2);
+                solutions.into_iter().map(|(exe, reg)| (exe.instructions, reg)).collect::<Vec<_>>()
+            };
+            assert_eq!(instructions(&plain), instructions(&memoized));
+        }
+    }
+
+    // Two parents with the same output share their children's entries
+    #[test]
+    fn same_parent_output_hits() {
+        let arena = ExecutionArena::new();
+        let root = arena.alloc_generation(vec![Execution::root(vec![vec![1, 1]])].into_iter().collect());
+        let root = root.iter().next().unwrap();
+        let (r0, r1) = (Register::new(0), Register::new(1));
+        let first = root.derive(Instruction::Mov(r0, r1), &arena);
+        let second = root.derive(Instruction::Mov(r1, r0), &arena);
+        assert_eq!(first.output, second.output);
+
+        let memo = MemoTable::new();
+        let child = |parent| Program {
+            parent: Some(parent),
+            instruction: Some(Instruction::Add(r0, r1)),
+            depth: 2,
+        };
+        let a = memo.execute(child(first), OverflowPolicy::Wrapping).unwrap();
+        let b = memo.execute(child(second), OverflowPolicy::Wrapping).unwrap();
+        assert_eq!((memo.hits(), memo.len()), (1, 1));
+        assert_eq!(a.output, b.output);
+        assert_eq!(b.linearize(), vec![Instruction::Mov(r1, r0), Instruction::Add(r0, r1)]);
+    }
+}
@@ -0,0 +1,36 @@
+use synthesizer::Synthesizer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryReport {
+    // Every generation the last run allocated, all of which stay live
+    pub arena_bytes: usize,
+    // Programs in the last generation
+    pub frontier_entries: usize,
+    // arena_bytes after one more generation growing at the latest rate
+    pub estimated_peak_bytes: usize,
+}
+
+impl Synthesizer {
+    // Estimated from the generations recorded by the last run's unseeded
+    // search; all zero before any run
+    pub fn memory_usage(&self) -> MemoryReport {
+        let stats = self.generation_stats.lock().unwrap();
+        let last = match stats.last() {
+            Some(last) => last,
+            None => return MemoryReport::default(),
+        };
+
+        let arena_bytes = stats.iter().map(|s| s.bytes).sum::<usize>();
+        let growth = match stats.len() {
+            1 => 1.0,
+            n => last.bytes as f64 / stats[n - 2].bytes.max(1) as f64,
+        };
+        let next_generation = (last.bytes as f64 * growth).min(usize::MAX as f64) as usize;
+
+        MemoryReport {
+            arena_bytes,
+            frontier_entries: last.frontier,
+            estimated_peak_bytes: arena_bytes.saturating_add(next_generation),
+        }
+    }
+}
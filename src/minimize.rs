@@ -0,0 +1,78 @@
+use arena::ExecutionArena;
+use synthesizer::SynthesizerBuilder;
+use testsuite::TestSuite;
+
+pub struct TestCaseMinimizer;
+
+impl TestCaseMinimizer {
+    // Greedily drop test cases that do not distinguish any programs: a case
+    // goes if the one-generation frontier, built with `synthesizer`'s
+    // filters and overflow policy, is just as large without it. Dropping a
+    // case can only merge executions, or let through ones the policy
+    // rejected on it, so an unchanged size means it was redundant.
+    pub fn minimize(spec: &TestSuite, synthesizer: &SynthesizerBuilder) -> TestSuite {
+        let mut suite = spec.clone();
+        let mut baseline = synthesizer.build(&suite).frontier_size(1);
+        let mut index = 0;
+
+        while index < suite.len() && suite.len() > 1 {
+            let mut trial = suite.clone();
+            trial.cases.remove(index);
+
+            let size = synthesizer.build(&trial).frontier_size(1);
+            if size == baseline {
+                suite = trial;
+                baseline = size;
+            } else {
+                index += 1;
+            }
+        }
+
+        suite
+    }
+}
+
+// Confirms that no program shorter than `depth` instructions satisfies
+// `spec`, by breadth-first search over every shorter length. Like
+// find_all_at_depth, `depth` does not count the builder's preamble. This
+// costs as much as the search that found the program, so it is for tests
+// and spot checks.
+pub fn verify_is_minimal(spec: &TestSuite, depth: usize, builder: &SynthesizerBuilder) -> bool {
+    !builder.build(spec).solvable_below(depth)
+}
+
+// The fewest registers, inputs included, that a program of at most `depth`
+// instructions needs to satisfy `spec`, trying max_registers from the
+// registers the search starts with upwards. None if no program that
+// short solves the spec however many registers it has.
+pub fn find_minimal_register_count(spec: &TestSuite, depth: usize, builder: &SynthesizerBuilder) -> Option<usize> {
+    let arena = ExecutionArena::new();
+    let start = builder.build(spec).start(&arena).output[0].len();
+
+    // Each instruction adds at most one register
+    (start..=start + depth)
+        .find(|&count| {
+            let capped = builder.clone().with_max_registers(count).with_grow_registers(false);
+            capped.build(spec).solvable_within(depth)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testsuite::TestCase;
+    use {OverflowPolicy, Value};
+
+    // Under Checked, an input that makes doubling and squaring overflow
+    // rejects those programs, so it distinguishes programs that wrapping
+    // arithmetic does not
+    #[test]
+    fn minimize_follows_overflow_policy() {
+        let spec = TestSuite::new(vec![TestCase::new(vec![Value::MAX], 0), TestCase::new(vec![3], 0)]);
+        let inputs = |builder: SynthesizerBuilder| {
+            TestCaseMinimizer::minimize(&spec, &builder).inputs().into_iter().flatten().collect::<Vec<_>>()
+        };
+        assert_eq!(inputs(SynthesizerBuilder::new()), vec![3]);
+        assert_eq!(inputs(SynthesizerBuilder::new().with_overflow_policy(OverflowPolicy::Checked)), vec![Value::MAX]);
+    }
+}
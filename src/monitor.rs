@@ -0,0 +1,91 @@
+use indexmap::IndexSet;
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use {Execution, Instruction, RegisterIndex};
+
+// Why a breadth-first search stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminationReason {
+    Solved,
+    Cancelled,
+    // Past max_depth, whether or not refinement then found something
+    DepthLimit,
+    GenerationLimit,
+    FrontierLimit,
+    FrontierEmpty,
+    RegisterCapReached,
+}
+
+// Observes a breadth-first search as it runs. With random restarts every
+// restart reports to the same monitor, possibly from several threads.
+pub trait SynthesizerMonitor: Debug + Send + Sync {
+    fn on_generation(&self, gen: usize, frontier: &IndexSet<Execution>);
+    // How many programs generation `gen` ran, before deduplication, just
+    // before on_generation. Seeded programs are not counted.
+    fn on_executions(&self, _gen: usize, _executions: usize) {}
+    fn on_solution(&self, exe: &Execution, reg: RegisterIndex);
+    fn on_termination(&self, reason: TerminationReason);
+}
+
+// Reports every event through the log crate when the log feature is on
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMonitor;
+
+impl SynthesizerMonitor for LoggingMonitor {
+    fn on_generation(&self, gen: usize, frontier: &IndexSet<Execution>) {
+        search_log!(info, "generation {}: {} programs", gen, frontier.len());
+    }
+
+    fn on_solution(&self, exe: &Execution, reg: RegisterIndex) {
+        search_log!(info, "solution of {} instructions, output in r{}", exe.depth(), reg);
+    }
+
+    fn on_termination(&self, reason: TerminationReason) {
+        search_log!(info, "search stopped: {:?}", reason);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorEvent {
+    Generation { generation: usize, frontier: usize },
+    Solution { instructions: Vec<Instruction>, register: RegisterIndex },
+    Termination(TerminationReason),
+}
+
+// Keeps every event in order, for tests
+#[derive(Debug, Default)]
+pub struct RecordingMonitor {
+    events: Mutex<Vec<MonitorEvent>>,
+}
+
+impl RecordingMonitor {
+    pub fn new() -> RecordingMonitor {
+        RecordingMonitor::default()
+    }
+
+    pub fn events(&self) -> Vec<MonitorEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl SynthesizerMonitor for RecordingMonitor {
+    fn on_generation(&self, gen: usize, frontier: &IndexSet<Execution>) {
+        self.events.lock().unwrap().push(MonitorEvent::Generation {
+            generation: gen,
+            frontier: frontier.len(),
+        });
+    }
+
+    fn on_solution(&self, exe: &Execution, reg: RegisterIndex) {
+        self.events.lock().unwrap().push(MonitorEvent::Solution {
+            instructions: exe.linearize(),
+            register: reg,
+        });
+    }
+
+    fn on_termination(&self, reason: TerminationReason) {
+        self.events.lock().unwrap().push(MonitorEvent::Termination(reason));
+    }
+}
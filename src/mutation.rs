@@ -0,0 +1,179 @@
+use rand::Rng;
+
+use rng::{SearchRng, SharedRng};
+use strategy::{CancellationToken, SearchStrategy};
+use testsuite::TestSuite;
+use {score, verify_output};
+use {Instruction, OwnedExecution, Register, RegisterIndex, Value};
+
+// Uniform pick from the programs add_one_instruction would generate for a
+// file of `register_count` registers
+pub fn random_instruction(register_count: usize, rng: &mut impl Rng) -> Instruction {
+    // One past the last register; at MAX_REGISTERS there is none to copy to
+    let new_register = Register::new(0).checked_add(register_count);
+    let copies = if new_register.is_some() { register_count } else { 0 };
+    let choices = copies + register_count + register_count * register_count * 3;
+    let mut pick = rng.gen_range(0..choices);
+
+    if let Some(new_register) = new_register {
+        if pick < register_count {
+            return Instruction::Mov(Register::new(pick), new_register);
+        }
+        pick -= register_count;
+    }
+
+    if pick < register_count {
+        return Instruction::Neg(Register::new(pick));
+    }
+    pick -= register_count;
+
+    let index = Register::new(pick / 3 / register_count);
+    let index2 = Register::new(pick / 3 % register_count);
+    match pick % 3 {
+        0 => Instruction::Mov(index, index2),
+        1 => Instruction::Add(index, index2),
+        _ => Instruction::Mul(index, index2),
+    }
+}
+
+// A valid program of exactly `length` uniformly chosen instructions
+pub fn random_program(inputs: Vec<Vec<Value>>, length: usize, rng: &mut impl Rng) -> OwnedExecution {
+    let mut register_count = inputs[0].len();
+    let instructions = (0..length)
+        .map(|_| {
+            let inst = random_instruction(register_count, rng);
+            register_count = inst.register_count_after(register_count);
+            inst
+        })
+        .collect();
+
+    OwnedExecution::replay(inputs, instructions)
+}
+
+fn register_count_at(exe: &OwnedExecution, position: usize) -> usize {
+    exe.instructions[..position]
+        .iter()
+        .fold(exe.inputs[0].len(), |count, inst| inst.register_count_after(count))
+}
+
+// Swap one instruction for another, keeping the program length. Replacements
+// that break a later instruction are retried.
+pub fn replace_one(exe: &OwnedExecution, rng: &mut impl Rng) -> OwnedExecution {
+    let len = exe.instructions.len();
+    assert!(len > 0, "cannot replace an instruction of the empty program");
+
+    loop {
+        let mut instructions = exe.instructions.clone();
+        let position = rng.gen_range(0..len);
+        instructions[position] = random_instruction(register_count_at(exe, position), rng);
+
+        if let Some(mutated) = OwnedExecution::try_replay(exe.inputs.clone(), instructions) {
+            return mutated;
+        }
+    }
+}
+
+// Replace, insert or delete one instruction. Mutations that leave a later
+// instruction reading a register that no longer exists are retried.
+pub fn mutate(exe: &OwnedExecution, rng: &mut impl Rng) -> OwnedExecution {
+    let len = exe.instructions.len();
+
+    loop {
+        let mut instructions = exe.instructions.clone();
+        match rng.gen_range(0..3) {
+            0 if len > 0 => return replace_one(exe, rng),
+            1 => {
+                let position = rng.gen_range(0..=len);
+                let inst = random_instruction(register_count_at(exe, position), rng);
+                instructions.insert(position, inst);
+            }
+            2 if len > 0 => {
+                instructions.remove(rng.gen_range(0..len));
+            }
+            _ => continue,
+        }
+
+        if let Some(mutated) = OwnedExecution::try_replay(exe.inputs.clone(), instructions) {
+            return mutated;
+        }
+    }
+}
+
+// Hill climbing over mutations, starting from the `pool` best candidates
+#[derive(Debug, Clone)]
+pub struct MutationSynthesizer {
+    pool: usize,
+    iterations: usize,
+    rng: Option<SharedRng>,
+}
+
+impl MutationSynthesizer {
+    pub fn new(pool: usize, iterations: usize) -> MutationSynthesizer {
+        MutationSynthesizer {
+            pool,
+            iterations,
+            rng: None,
+        }
+    }
+
+    // Used when climbing as a strategy of its own; refine takes the
+    // generator from its caller
+    pub fn with_rng(mut self, rng: SharedRng) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    pub fn pool(&self) -> usize {
+        self.pool
+    }
+
+    pub fn refine(
+        &self,
+        mut candidates: Vec<OwnedExecution>,
+        outputs: &[Value],
+        rng: &mut impl Rng,
+        cancel: &CancellationToken,
+    ) -> Option<(OwnedExecution, RegisterIndex)> {
+        candidates.sort_by(|a, b| score(&b.output, outputs).total_cmp(&score(&a.output, outputs)));
+        candidates.truncate(self.pool);
+
+        for mut current in candidates {
+            let mut current_score = score(&current.output, outputs);
+
+            for _ in 0..self.iterations {
+                if cancel.is_cancelled() {
+                    return None;
+                }
+
+                let mutated = mutate(&current, rng);
+                let mutated_score = score(&mutated.output, outputs);
+                if mutated_score > current_score {
+                    if let Some(reg) = verify_output(&mutated.output, outputs) {
+                        return Some((mutated, reg));
+                    }
+                    current = mutated;
+                    current_score = mutated_score;
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl SearchStrategy for MutationSynthesizer {
+    // Climbs from the empty program
+    fn run(
+        &self,
+        spec: &TestSuite,
+        cancel: CancellationToken,
+    ) -> Option<(OwnedExecution, RegisterIndex)> {
+        let outputs = spec.outputs();
+        let start = OwnedExecution::replay(spec.inputs(), vec![]);
+        if let Some(reg) = verify_output(&start.output, &outputs) {
+            return Some((start, reg));
+        }
+
+        self.refine(vec![start], &outputs, &mut SearchRng::from_config(&self.rng), &cancel)
+    }
+}
@@ -0,0 +1,92 @@
+use exhaustive::{assignments, run_on};
+use session::SynthesizerSession;
+use synthesizer::{SynthesisError, Synthesizer, SynthesizerBuilder};
+use testsuite::{TestCase, TestSuite};
+use {Instruction, OwnedExecution, RegisterIndex, Value};
+
+// Decides whether a program computes the intended function on every input,
+// not just the test cases
+pub trait EquivalenceOracle {
+    fn is_equivalent(&self, instructions: &[Instruction], output_register: RegisterIndex) -> bool;
+
+    // An input the program gets wrong and the output it should give, asked
+    // for once is_equivalent has said no
+    fn counterexample(&self, instructions: &[Instruction], output_register: RegisterIndex) -> Option<TestCase>;
+}
+
+// Compares against `f` on every combination of `domain` values, as
+// exhaustively_verify does, but for one output register
+pub struct ExhaustiveOracle<F> {
+    pub domain: Vec<Value>,
+    pub n_inputs: usize,
+    pub f: F,
+}
+
+impl<F: Fn(&[Value]) -> Value> EquivalenceOracle for ExhaustiveOracle<F> {
+    fn is_equivalent(&self, instructions: &[Instruction], output_register: RegisterIndex) -> bool {
+        self.counterexample(instructions, output_register).is_none()
+    }
+
+    fn counterexample(&self, instructions: &[Instruction], output_register: RegisterIndex) -> Option<TestCase> {
+        assignments(vec![self.domain.clone(); self.n_inputs]).find_map(|inputs| {
+            let expected = (self.f)(&inputs);
+            if run_on(instructions, &inputs)[output_register.as_index()] == expected {
+                None
+            } else {
+                Some(TestCase::new(inputs, expected))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OracleVerifiedSolution {
+    pub execution: OwnedExecution,
+    pub register: RegisterIndex,
+    // The original test cases followed by every counterexample added
+    pub spec: TestSuite,
+    // Candidates the oracle was asked about, including the accepted one
+    pub rounds: usize,
+}
+
+impl Synthesizer {
+    // Counterexample-guided search: find a program that passes the test
+    // cases, ask `oracle` about it, and on a rejection add the oracle's
+    // counterexample to the spec and search again. The search continues
+    // from where the last round stopped, as in SynthesizerSession, so the
+    // accepted program need not be the shortest one.
+    pub fn find_with_oracle<O: EquivalenceOracle>(
+        spec: &TestSuite,
+        oracle: &O,
+        builder: &SynthesizerBuilder,
+    ) -> Result<OracleVerifiedSolution, SynthesisError> {
+        let mut session = SynthesizerSession::new(builder.clone(), spec.clone());
+        let mut round = 0;
+
+        loop {
+            round += 1;
+            let (execution, register) = match session.run()? {
+                Some(solution) => solution,
+                None => return Err(SynthesisError::NoProgramFound { rounds: round - 1 }),
+            };
+            if oracle.is_equivalent(&execution.instructions, register) {
+                return Ok(OracleVerifiedSolution {
+                    execution,
+                    register,
+                    spec: session.spec().clone(),
+                    rounds: round,
+                });
+            }
+
+            // Without a case the program fails, the next round could find it again
+            let case = oracle
+                .counterexample(&execution.instructions, register)
+                .filter(|case| {
+                    let arity = execution.inputs[0].len();
+                    case.inputs.len() != arity || execution.evaluate(register, &case.inputs) != case.output
+                })
+                .ok_or(SynthesisError::MissingCounterexample { round })?;
+            session.add_test_case(case.inputs, case.output);
+        }
+    }
+}
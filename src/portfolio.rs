@@ -0,0 +1,40 @@
+use std::sync::mpsc;
+use std::thread;
+
+use strategy::{CancellationToken, SearchStrategy};
+use testsuite::TestSuite;
+use {OwnedExecution, RegisterIndex};
+
+// Races several strategies against each other on their own threads
+pub struct SynthesizerPortfolio {
+    strategies: Vec<Box<dyn SearchStrategy>>,
+}
+
+impl SynthesizerPortfolio {
+    pub fn new(strategies: Vec<Box<dyn SearchStrategy>>) -> SynthesizerPortfolio {
+        SynthesizerPortfolio { strategies }
+    }
+
+    // First solution wins; every other strategy is cancelled and joined
+    // before returning
+    pub fn run(&self, spec: &TestSuite) -> Option<(OwnedExecution, RegisterIndex)> {
+        let cancel = CancellationToken::new();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for strategy in &self.strategies {
+                let sender = sender.clone();
+                let cancel = cancel.clone();
+                scope.spawn(move || {
+                    // The receiver hangs up once a winner is found
+                    let _ = sender.send(strategy.run(spec, cancel));
+                });
+            }
+            drop(sender);
+
+            let winner = receiver.iter().find(Option::is_some).and_then(|result| result);
+            cancel.cancel();
+            winner
+        })
+    }
+}
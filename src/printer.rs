@@ -0,0 +1,255 @@
+use std::fmt::Debug;
+use std::io::{self, Write};
+use std::iter;
+
+use arena::ExecutionArena;
+use {compute_ordering, flatten_fused, Execution, Instruction, OwnedExecution, RegisterIndex};
+
+// Writes a solved program in some output format, with fused instructions
+// written out as their parts
+pub trait ProgramPrinter: Debug + Send + Sync {
+    fn print(&self, exe: &Execution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()>;
+
+    // print for a solution that has left its arena, as from a batch or a
+    // cache, by replaying it into a new one
+    fn print_owned(&self, exe: &OwnedExecution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()> {
+        let arena = ExecutionArena::new();
+        let root = arena.alloc_generation(iter::once(Execution::root(exe.inputs.clone())).collect());
+        let leaf = exe
+            .instructions
+            .iter()
+            .fold(root.iter().next().unwrap(), |leaf, inst| leaf.derive(inst.clone(), &arena));
+        self.print(leaf, output_register, writer)
+    }
+}
+
+fn mnemonic(inst: &Instruction) -> &'static str {
+    match *inst {
+        Instruction::Mov(..) => "mov",
+        Instruction::Add(..) => "add",
+        Instruction::Mul(..) => "mul",
+        Instruction::Neg(..) => "neg",
+        Instruction::Fused(..) => "fused",
+    }
+}
+
+// One `dst = expression` line per instruction, then the output register,
+// with input registers called by `names` (as from named_testcases!) and
+// the registers the program adds t0, t1 and so on. Fused instructions
+// print as their parts.
+pub fn pretty_print(instructions: &[Instruction], output_register: RegisterIndex, names: &[&str]) -> String {
+    // Numbered in the order the program first writes them
+    let temporaries = compute_ordering(instructions)
+        .into_iter()
+        .filter(|reg| reg.as_index() >= names.len())
+        .collect::<Vec<_>>();
+    let name = |reg: RegisterIndex| match names.get(reg.as_index()) {
+        Some(name) => name.to_string(),
+        None => match temporaries.iter().position(|&temp| temp == reg) {
+            Some(index) => format!("t{}", index),
+            None => format!("r{}", reg),
+        },
+    };
+
+    let mut text = String::new();
+    for inst in flatten_fused(instructions) {
+        let line = match inst {
+            Instruction::Mov(r1, r2) => format!("{} = {}", name(r2), name(r1)),
+            Instruction::Add(r1, r2) => format!("{} = {} + {}", name(r2), name(r2), name(r1)),
+            Instruction::Mul(r1, r2) => format!("{} = {} * {}", name(r2), name(r2), name(r1)),
+            Instruction::Neg(r) => format!("{} = -{}", name(r), name(r)),
+            Instruction::Fused(..) => unreachable!(),
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text.push_str(&format!("return {}", name(output_register)));
+    text
+}
+
+// The program's own Display, as the CLI prints it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextPrinter;
+
+impl ProgramPrinter for TextPrinter {
+    fn print(&self, exe: &Execution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "found{}\noutput in r{}", exe, output_register)
+    }
+}
+
+// {"instructions": [{"op": "add", "src": 0, "dst": 1}, ...], "output_register": 1}
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonPrinter;
+
+impl ProgramPrinter for JsonPrinter {
+    fn print(&self, exe: &Execution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "{{\"instructions\": [")?;
+        for (i, inst) in flatten_fused(&exe.linearize()).iter().enumerate() {
+            if i > 0 {
+                write!(writer, ", ")?;
+            }
+            match *inst {
+                Instruction::Mov(r1, r2) | Instruction::Add(r1, r2) | Instruction::Mul(r1, r2) => write!(
+                    writer,
+                    "{{\"op\": \"{}\", \"src\": {}, \"dst\": {}}}",
+                    mnemonic(inst),
+                    r1,
+                    r2
+                )?,
+                Instruction::Neg(r) => write!(writer, "{{\"op\": \"neg\", \"reg\": {}}}", r)?,
+                Instruction::Fused(..) => unreachable!(),
+            }
+        }
+        writeln!(writer, "], \"output_register\": {}}}", output_register)
+    }
+}
+
+// Dataflow graph: one node per input and per computed value, with edges
+// from the values each instruction reads. Movs only rename values.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DotPrinter;
+
+impl ProgramPrinter for DotPrinter {
+    fn print(&self, exe: &Execution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "digraph program {{")?;
+        let mut current = (0..exe.input_count()).map(|reg| format!("in{}", reg)).collect::<Vec<_>>();
+        for node in &current {
+            writeln!(writer, "    {} [shape=box];", node)?;
+        }
+
+        for (i, inst) in flatten_fused(&exe.linearize()).iter().enumerate() {
+            let node = format!("n{}", i);
+            let (dst, sources) = match *inst {
+                Instruction::Mov(r1, r2) => {
+                    let value = current[r1.as_index()].clone();
+                    if r2.as_index() == current.len() {
+                        current.push(value);
+                    } else {
+                        current[r2.as_index()] = value;
+                    }
+                    continue;
+                }
+                Instruction::Add(r1, r2) | Instruction::Mul(r1, r2) => (r2, vec![r1, r2]),
+                Instruction::Neg(r) => (r, vec![r]),
+                Instruction::Fused(..) => unreachable!(),
+            };
+
+            writeln!(writer, "    {} [label=\"{}\"];", node, mnemonic(inst))?;
+            for src in sources {
+                writeln!(writer, "    {} -> {};", current[src.as_index()], node)?;
+            }
+            current[dst.as_index()] = node;
+        }
+
+        writeln!(writer, "    out [shape=box];")?;
+        writeln!(writer, "    {} -> out;", current[output_register.as_index()])?;
+        writeln!(writer, "}}")
+    }
+}
+
+// Intel-syntax x86-64 for `long f(long *regs)`, where `regs` holds the
+// inputs followed by room for the registers the program adds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct X86Printer;
+
+impl ProgramPrinter for X86Printer {
+    fn print(&self, exe: &Execution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()> {
+        let slot = |reg: RegisterIndex| format!("qword ptr [rdi + {}]", reg.as_index() * 8);
+
+        writeln!(
+            writer,
+            "; regs: {} inputs, {} registers in total",
+            exe.input_count(),
+            exe.output[0].len()
+        )?;
+        writeln!(writer, "synthesized:")?;
+        for inst in flatten_fused(&exe.linearize()) {
+            match inst {
+                Instruction::Mov(r1, r2) => {
+                    writeln!(writer, "    mov rax, {}", slot(r1))?;
+                    writeln!(writer, "    mov {}, rax", slot(r2))?;
+                }
+                Instruction::Add(r1, r2) => {
+                    writeln!(writer, "    mov rax, {}", slot(r1))?;
+                    writeln!(writer, "    add {}, rax", slot(r2))?;
+                }
+                Instruction::Mul(r1, r2) => {
+                    writeln!(writer, "    mov rax, {}", slot(r2))?;
+                    writeln!(writer, "    imul rax, {}", slot(r1))?;
+                    writeln!(writer, "    mov {}, rax", slot(r2))?;
+                }
+                Instruction::Neg(r) => writeln!(writer, "    neg {}", slot(r))?,
+                Instruction::Fused(..) => unreachable!(),
+            }
+        }
+        writeln!(writer, "    mov rax, {}", slot(output_register))?;
+        writeln!(writer, "    ret")
+    }
+}
+
+// A WebAssembly text module exporting `synthesized`, one i64 parameter per
+// input and a local for each added register. i64 arithmetic wraps the same
+// way the interpreter does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WatPrinter;
+
+impl ProgramPrinter for WatPrinter {
+    fn print(&self, exe: &Execution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "(module")?;
+        write!(writer, "  (func $synthesized (export \"synthesized\")")?;
+        for reg in 0..exe.input_count() {
+            write!(writer, " (param $r{} i64)", reg)?;
+        }
+        write!(writer, " (result i64)")?;
+        for reg in exe.input_count()..exe.output[0].len() {
+            write!(writer, " (local $r{} i64)", reg)?;
+        }
+        writeln!(writer)?;
+
+        for inst in flatten_fused(&exe.linearize()) {
+            match inst {
+                Instruction::Mov(r1, r2) => writeln!(writer, "    local.get $r{} local.set $r{}", r1, r2)?,
+                Instruction::Add(r1, r2) => {
+                    writeln!(writer, "    local.get $r{} local.get $r{} i64.add local.set $r{}", r1, r2, r2)?
+                }
+                Instruction::Mul(r1, r2) => {
+                    writeln!(writer, "    local.get $r{} local.get $r{} i64.mul local.set $r{}", r1, r2, r2)?
+                }
+                Instruction::Neg(r) => writeln!(writer, "    i64.const 0 local.get $r{} i64.sub local.set $r{}", r, r)?,
+                Instruction::Fused(..) => unreachable!(),
+            }
+        }
+
+        writeln!(writer, "    local.get $r{}))", output_register)?;
+        writeln!(writer, ")")
+    }
+}
+
+// The assembly dialects Asm output can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsmTarget {
+    X86_64,
+}
+
+// One of the printers above by name, for choosing a format from
+// configuration or the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Asm(AsmTarget),
+    Wat,
+    Dot,
+}
+
+impl ProgramPrinter for OutputFormat {
+    fn print(&self, exe: &Execution, output_register: RegisterIndex, writer: &mut dyn Write) -> io::Result<()> {
+        match *self {
+            OutputFormat::Text => TextPrinter.print(exe, output_register, writer),
+            OutputFormat::Json => JsonPrinter.print(exe, output_register, writer),
+            OutputFormat::Asm(AsmTarget::X86_64) => X86Printer.print(exe, output_register, writer),
+            OutputFormat::Wat => WatPrinter.print(exe, output_register, writer),
+            OutputFormat::Dot => DotPrinter.print(exe, output_register, writer),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use indexmap::IndexSet;
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use arena::ExecutionArena;
+use synthesizer::SynthesizerBuilder;
+use testsuite::TestSuite;
+use {execute_with, par_frontier, Execution, InstructionVariant, Program};
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    pub time_per_variant: HashMap<InstructionVariant, Duration>,
+    // Instruction applications per variant, one per test case
+    pub executions_per_variant: HashMap<InstructionVariant, usize>,
+}
+
+impl ProfileReport {
+    pub fn total(&self) -> Duration {
+        self.time_per_variant.values().sum()
+    }
+}
+
+// Re-run the program behind `exe` on the inputs of `spec`, timing each
+// instruction over all the test cases at once so the clock overhead stays
+// small
+pub fn profile_execute(exe: &Execution, spec: &TestSuite) -> ProfileReport {
+    let mut report = ProfileReport::default();
+    let mut testcases = spec.inputs();
+
+    for inst in exe.linearize() {
+        let start = Instant::now();
+        for testcase in &mut testcases {
+            inst.apply(testcase);
+        }
+        let elapsed = start.elapsed();
+
+        *report.time_per_variant.entry(inst.variant()).or_insert_with(Duration::default) += elapsed;
+        *report.executions_per_variant.entry(inst.variant()).or_insert(0) += testcases.len();
+    }
+
+    report
+}
+
+// Nanoseconds spent in each phase of breadth-first search
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimingProfile {
+    // Expanding parents into programs, with the builder's filters
+    pub instruction_gen_ns: u64,
+    // Running the programs that are not no-ops and checking domains
+    pub execution_ns: u64,
+    pub dedup_ns: u64,
+    // Checking each generation for a solution
+    pub verify_ns: u64,
+    // Everything, including allocating generations
+    pub total_ns: u64,
+}
+
+fn nanos(elapsed: Duration) -> u64 {
+    elapsed.as_nanos() as u64
+}
+
+// Run up to `depth` generations of the unseeded search that `builder`
+// would do on `spec`, one phase at a time so each can be timed. Stops
+// after a generation with a solution or an empty one. Each phase
+// collects its results before the next begins, which the search itself
+// does not, and it runs on rayon's global pool even if `builder` set a
+// thread count.
+pub fn profile_generation(spec: &TestSuite, depth: usize, builder: &SynthesizerBuilder) -> TimingProfile {
+    builder.install(|| {
+        let started = Instant::now();
+        let synthesizer = builder.build(spec);
+        let mut profile = TimingProfile::default();
+
+        let arena = ExecutionArena::new();
+        let mut starts = IndexSet::new();
+        starts.insert(synthesizer.start(&arena));
+        let mut frontier = arena.alloc_generation(starts);
+
+        for _ in 0..depth {
+            let phase = Instant::now();
+            let programs = par_frontier(frontier)
+                .flat_map(|parent| synthesizer.expand(parent, None))
+                .collect::<Vec<Program>>();
+            profile.instruction_gen_ns += nanos(phase.elapsed());
+
+            let phase = Instant::now();
+            let executions = programs
+                .into_par_iter()
+                .filter(|program| !program.is_noop_with(synthesizer.config.overflow))
+                .filter_map(|program| execute_with(program, synthesizer.config.overflow))
+                .filter(|exe| synthesizer.within_domains(exe))
+                .collect::<Vec<_>>();
+            profile.execution_ns += nanos(phase.elapsed());
+
+            let phase = Instant::now();
+            let next = synthesizer.collect_generation(executions.into_par_iter());
+            profile.dedup_ns += nanos(phase.elapsed());
+
+            let phase = Instant::now();
+            let solved = par_frontier(&next).any(|exe| synthesizer.output_register(exe).is_some());
+            profile.verify_ns += nanos(phase.elapsed());
+
+            if solved || next.is_empty() {
+                break;
+            }
+            frontier = arena.alloc_generation(next);
+        }
+
+        profile.total_ns = nanos(started.elapsed());
+        profile
+    })
+}
@@ -0,0 +1,73 @@
+// Per-generation status for the breadth-first search. Without the
+// `indicatif` feature every method is a no-op.
+
+#[cfg(feature = "indicatif")]
+mod bar {
+    use indicatif::{ProgressBar, ProgressStyle};
+    use std::time::Instant;
+
+    pub(crate) struct SearchProgress {
+        bar: Option<ProgressBar>,
+        started: Instant,
+        programs: usize,
+    }
+
+    impl SearchProgress {
+        pub(crate) fn new(enabled: bool) -> SearchProgress {
+            let bar = if enabled {
+                let bar = ProgressBar::new_spinner();
+                if let Ok(style) = ProgressStyle::with_template("{spinner} [{elapsed_precise}] {msg}") {
+                    bar.set_style(style);
+                }
+                Some(bar)
+            } else {
+                None
+            };
+            SearchProgress {
+                bar,
+                started: Instant::now(),
+                programs: 0,
+            }
+        }
+
+        pub(crate) fn generation(&mut self, generation: usize, frontier: usize) {
+            self.programs += frontier;
+            if let Some(ref bar) = self.bar {
+                let elapsed = self.started.elapsed();
+                let rate = self.programs as f64 / elapsed.as_secs_f64().max(1e-9);
+                bar.inc(1);
+                bar.set_message(format!(
+                    "generation {}, frontier {}, {:.1}s, {:.0} programs/s",
+                    generation,
+                    frontier,
+                    elapsed.as_secs_f64(),
+                    rate
+                ));
+            }
+        }
+    }
+
+    // Leaves the last status on screen however the search ends
+    impl Drop for SearchProgress {
+        fn drop(&mut self) {
+            if let Some(ref bar) = self.bar {
+                bar.finish();
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "indicatif"))]
+mod bar {
+    pub(crate) struct SearchProgress;
+
+    impl SearchProgress {
+        pub(crate) fn new(_enabled: bool) -> SearchProgress {
+            SearchProgress
+        }
+
+        pub(crate) fn generation(&mut self, _generation: usize, _frontier: usize) {}
+    }
+}
+
+pub(crate) use self::bar::SearchProgress;
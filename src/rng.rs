@@ -0,0 +1,81 @@
+use rand::rngs::ThreadRng;
+use rand::{Error, RngCore};
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+// A caller-supplied generator shared by every stochastic part of a search.
+// Clones draw from the same stream, so seeding it once makes a single-
+// threaded run reproducible.
+#[derive(Clone)]
+pub struct SharedRng(Arc<Mutex<Box<dyn RngCore + Send>>>);
+
+impl SharedRng {
+    pub fn new(rng: Box<dyn RngCore + Send>) -> SharedRng {
+        SharedRng(Arc::new(Mutex::new(rng)))
+    }
+}
+
+impl fmt::Debug for SharedRng {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SharedRng")
+    }
+}
+
+impl RngCore for SharedRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.lock().unwrap().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.lock().unwrap().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.lock().unwrap().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.lock().unwrap().try_fill_bytes(dest)
+    }
+}
+
+// The injected generator if there is one, otherwise the thread's own
+pub(crate) enum SearchRng {
+    Shared(SharedRng),
+    Thread(ThreadRng),
+}
+
+impl SearchRng {
+    pub(crate) fn from_config(rng: &Option<SharedRng>) -> SearchRng {
+        match *rng {
+            Some(ref rng) => SearchRng::Shared(rng.clone()),
+            None => SearchRng::Thread(::rand::thread_rng()),
+        }
+    }
+
+    fn inner(&mut self) -> &mut dyn RngCore {
+        match *self {
+            SearchRng::Shared(ref mut rng) => rng,
+            SearchRng::Thread(ref mut rng) => rng,
+        }
+    }
+}
+
+impl RngCore for SearchRng {
+    fn next_u32(&mut self) -> u32 {
+        self.inner().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.inner().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.inner().try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,65 @@
+use mutation::random_program;
+use rng::{SearchRng, SharedRng};
+use strategy::{CancellationToken, SearchStrategy};
+use testsuite::TestSuite;
+use verify_output;
+use {OwnedExecution, RegisterIndex};
+
+// Monte Carlo stand-in for breadth-first search: at each length, from one
+// instruction up, try `samples_per_gen` programs with every instruction
+// drawn uniformly from what add_one_instruction would offer. Nothing is
+// deduplicated and a solution can be missed, but the cost per length stays
+// fixed however large the instruction space gets.
+#[derive(Debug, Clone)]
+pub struct RandomSamplingStrategy {
+    samples_per_gen: usize,
+    max_depth: Option<usize>,
+    rng: Option<SharedRng>,
+}
+
+impl RandomSamplingStrategy {
+    // Without a `max_depth` it samples until cancelled
+    pub fn new(samples_per_gen: usize, max_depth: Option<usize>) -> RandomSamplingStrategy {
+        RandomSamplingStrategy {
+            samples_per_gen,
+            max_depth,
+            rng: None,
+        }
+    }
+
+    pub fn with_rng(mut self, rng: SharedRng) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+}
+
+impl SearchStrategy for RandomSamplingStrategy {
+    fn run(
+        &self,
+        spec: &TestSuite,
+        cancel: CancellationToken,
+    ) -> Option<(OwnedExecution, RegisterIndex)> {
+        let rng = &mut SearchRng::from_config(&self.rng);
+        let inputs = spec.inputs();
+        let outputs = &spec.outputs();
+        let start = OwnedExecution::replay(inputs.clone(), vec![]);
+        if let Some(reg) = verify_output(&start.output, outputs) {
+            return Some((start, reg));
+        }
+
+        let mut depth = 1;
+        while self.max_depth.is_none_or(|max_depth| depth <= max_depth) {
+            for _ in 0..self.samples_per_gen {
+                if cancel.is_cancelled() {
+                    return None;
+                }
+                let exe = random_program(inputs.clone(), depth, rng);
+                if let Some(reg) = verify_output(&exe.output, outputs) {
+                    return Some((exe, reg));
+                }
+            }
+            depth += 1;
+        }
+        None
+    }
+}
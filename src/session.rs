@@ -0,0 +1,98 @@
+use synthesizer::{SynthesisResult, Synthesizer, SynthesizerBuilder};
+use testsuite::{TestCase, TestSuite};
+use Value;
+
+// A spec that grows between runs. Each run continues breadth-first from
+// the frontier the last one stopped at, rerun on the enlarged spec, rather
+// than from the inputs. Adding a case drops the programs that fail it,
+// those with no register holding its output, so later runs only extend
+// programs that already account for every case. A longer program built on
+// a dropped one could still have passed, so a run can miss the shortest
+// solution that a fresh search would find. If no program passes, the next
+// run starts over, as it does with random restarts.
+#[derive(Debug)]
+pub struct SynthesizerSession {
+    builder: SynthesizerBuilder,
+    spec: TestSuite,
+    synthesizer: Synthesizer,
+}
+
+impl SynthesizerSession {
+    pub fn new(builder: SynthesizerBuilder, spec: TestSuite) -> SynthesizerSession {
+        let mut builder = builder;
+        builder.resumable = true;
+        let synthesizer = builder.build(&spec);
+        SynthesizerSession {
+            builder,
+            spec,
+            synthesizer,
+        }
+    }
+
+    pub fn spec(&self) -> &TestSuite {
+        &self.spec
+    }
+
+    pub fn synthesizer(&self) -> &Synthesizer {
+        &self.synthesizer
+    }
+
+    pub fn add_test_case(&mut self, inputs: Vec<Value>, output: Value) -> &mut Self {
+        self.spec.push(TestCase::new(inputs, output));
+        let synthesizer = self.builder.build(&self.spec);
+        let expected = synthesizer.outputs[synthesizer.outputs.len() - 1];
+        let state = self
+            .synthesizer
+            .snapshot()
+            .with_inputs(&synthesizer.inputs)
+            .filtered(|exe| exe.output[exe.output.len() - 1].contains(&expected));
+        if !state.frontier().is_empty() {
+            synthesizer.restore(state);
+        }
+        self.synthesizer = synthesizer;
+        self
+    }
+
+    pub fn run(&self) -> SynthesisResult {
+        self.synthesizer.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SynthesizerSession;
+    use testsuite::{TestCase, TestSuite};
+    use {Instruction, Register, SynthesizerBuilder};
+
+    fn fourth_power() -> TestSuite {
+        TestSuite::new(vec![TestCase::new(vec![2], 16), TestCase::new(vec![3], 81)])
+    }
+
+    // The run stops with the one-instruction parents of x^4; [-1] -> 1
+    // keeps mul r0 r0 but drops add r0 r0, which leaves -2
+    #[test]
+    fn new_case_filters_frontier() {
+        let mut session = SynthesizerSession::new(SynthesizerBuilder::new(), fourth_power());
+        assert!(session.run().unwrap().is_some());
+
+        session.add_test_case(vec![-1], 1);
+        let state = session.synthesizer().snapshot();
+        let r0 = Register::new(0);
+        assert_eq!(state.generation(), 1);
+        assert!(state.frontier().iter().all(|exe| exe.output[2].contains(&1)));
+        assert!(state.frontier().iter().any(|exe| exe.instructions == vec![Instruction::Mul(r0, r0)]));
+        assert!(state.frontier().iter().all(|exe| exe.instructions != vec![Instruction::Add(r0, r0)]));
+
+        let (exe, _) = session.run().unwrap().unwrap();
+        assert_eq!(exe.instructions, vec![Instruction::Mul(r0, r0), Instruction::Mul(r0, r0)]);
+    }
+
+    #[test]
+    fn session_starts_over_when_no_program_passes() {
+        let mut session = SynthesizerSession::new(SynthesizerBuilder::new(), fourth_power());
+        assert!(session.run().unwrap().is_some());
+
+        session.add_test_case(vec![3], 1000);
+        assert_eq!(session.synthesizer().snapshot().generation(), 0);
+    }
+}
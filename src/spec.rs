@@ -0,0 +1,248 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use synthesizer::Synthesizer;
+use testsuite::{TestCase, TestSuite};
+use Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecError {
+    Empty,
+    NoInputs,
+    // Case `case` has a different number of inputs than the first one
+    InconsistentArity { case: usize, expected: usize, found: usize },
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SpecError::Empty => write!(f, "the spec has no test cases"),
+            SpecError::NoInputs => write!(f, "the test cases have no inputs"),
+            SpecError::InconsistentArity { case, expected, found } => write!(
+                f,
+                "test case {} has {} inputs, expected {}",
+                case, found, expected
+            ),
+        }
+    }
+}
+
+impl Error for SpecError {}
+
+// Things that do not stop a search but suggest the spec is not what was
+// meant
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecWarning {
+    InconsistentArity { case: usize, expected: usize, found: usize },
+    TooFewCases { count: usize },
+    // Case `second` repeats the inputs of case `first`
+    DuplicateInputs { first: usize, second: usize },
+    // Every case expects the same output, which needs no real program
+    ConstantOutput(Value),
+    // Every output is positive, or every output negative
+    SameSignOutputs,
+    // Input pairs that hold the same value in every case
+    AliasedInputs(Vec<(usize, usize)>),
+}
+
+impl fmt::Display for SpecWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SpecWarning::InconsistentArity { case, expected, found } => write!(
+                f,
+                "test case {} has {} inputs, expected {}",
+                case, found, expected
+            ),
+            SpecWarning::TooFewCases { count } => write!(f, "only {} test case(s); at least 2 are needed", count),
+            SpecWarning::DuplicateInputs { first, second } => {
+                write!(f, "test case {} repeats the inputs of test case {}", second, first)
+            }
+            SpecWarning::ConstantOutput(value) => write!(f, "every test case expects {}", value),
+            SpecWarning::SameSignOutputs => write!(f, "every expected output has the same sign"),
+            SpecWarning::AliasedInputs(ref pairs) => {
+                write!(f, "inputs are equal in every test case:")?;
+                for &(a, b) in pairs {
+                    write!(f, " r{}=r{}", a, b)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Pairs of input registers, lower index first, that never differ. The spec
+// cannot tell a program reading one from a program reading the other.
+pub fn detect_aliased_inputs(inputs: &[Vec<Value>]) -> Vec<(usize, usize)> {
+    let arity = inputs.iter().map(Vec::len).min().unwrap_or(0);
+    let mut pairs = Vec::new();
+
+    for a in 0..arity {
+        for b in a + 1..arity {
+            if inputs.iter().all(|row| row[a] == row[b]) {
+                pairs.push((a, b));
+            }
+        }
+    }
+    pairs
+}
+
+impl Synthesizer {
+    // Aliased inputs of the spec as given, after any input preprocessing.
+    // Registers an initial transform or with_register_file_init adds are
+    // the builder's doing, not the user's, so they are left out.
+    pub fn check_register_aliasing(&self) -> Vec<(usize, usize)> {
+        detect_aliased_inputs(&self.spec.inputs())
+    }
+
+    // Checks for specs that are valid but probably mistaken. Nothing here
+    // stops run(), so callers decide what to do with the warnings.
+    pub fn validate_spec(&self) -> Result<(), Vec<SpecWarning>> {
+        let mut warnings = Vec::new();
+        let cases = self.spec.cases();
+
+        if let Some(first) = cases.first() {
+            let expected = first.inputs.len();
+            for (case, test) in cases.iter().enumerate() {
+                if test.inputs.len() != expected {
+                    warnings.push(SpecWarning::InconsistentArity {
+                        case,
+                        expected,
+                        found: test.inputs.len(),
+                    });
+                }
+            }
+        }
+
+        if cases.len() < 2 {
+            warnings.push(SpecWarning::TooFewCases { count: cases.len() });
+        }
+
+        let mut seen = HashMap::new();
+        for (case, test) in cases.iter().enumerate() {
+            if let Some(&first) = seen.get(&test.inputs) {
+                warnings.push(SpecWarning::DuplicateInputs { first, second: case });
+            } else {
+                seen.insert(&test.inputs, case);
+            }
+        }
+
+        if let Some(first) = cases.first() {
+            if cases.len() > 1 && cases.iter().all(|test| test.output == first.output) {
+                warnings.push(SpecWarning::ConstantOutput(first.output));
+            } else if cases.iter().all(|test| test.output > 0) || cases.iter().all(|test| test.output < 0) {
+                warnings.push(SpecWarning::SameSignOutputs);
+            }
+        }
+
+        let aliased = self.check_register_aliasing();
+        if !aliased.is_empty() {
+            warnings.push(SpecWarning::AliasedInputs(aliased));
+        }
+
+        if warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(warnings)
+        }
+    }
+}
+
+fn invalid_data(path: &Path, line: usize, message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: {}", path.display(), line, message))
+}
+
+// Collects test cases from several sources into one TestSuite
+#[derive(Debug, Clone, Default)]
+pub struct SynthesisSpecBuilder {
+    cases: Vec<TestCase>,
+}
+
+impl SynthesisSpecBuilder {
+    pub fn new() -> SynthesisSpecBuilder {
+        SynthesisSpecBuilder::default()
+    }
+
+    pub fn add_case(&mut self, inputs: Vec<Value>, output: Value) -> &mut Self {
+        self.cases.push(TestCase::new(inputs, output));
+        self
+    }
+
+    // One case per line: `input0,input1,...,output`. Blank lines and `#`
+    // comments are skipped.
+    pub fn add_from_csv(&mut self, path: &Path) -> Result<&mut Self, io::Error> {
+        let text = fs::read_to_string(path)?;
+        let mut cases = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values = line
+                .split(',')
+                .map(|field| field.trim().parse::<Value>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| invalid_data(path, number + 1, e.to_string()))?;
+            let (output, inputs) = values
+                .split_last()
+                .ok_or_else(|| invalid_data(path, number + 1, "expected inputs and an output".to_string()))?;
+            cases.push(TestCase::new(inputs.to_vec(), *output));
+        }
+
+        // Nothing from a file that fails halfway through
+        self.cases.extend(cases);
+        Ok(self)
+    }
+
+    // `n` cases with inputs drawn uniformly from `domain` and outputs
+    // computed by `f`. The input count is taken from the cases added so far.
+    pub fn add_random(
+        &mut self,
+        n: usize,
+        domain: &[Value],
+        f: impl Fn(&[Value]) -> Value,
+        rng: &mut impl Rng,
+    ) -> &mut Self {
+        let arity = self
+            .cases
+            .first()
+            .map(|case| case.inputs.len())
+            .expect("add_random needs an earlier case to know how many inputs to generate");
+
+        for _ in 0..n {
+            let inputs = (0..arity)
+                .map(|_| *domain.choose(rng).expect("the domain has no values to draw from"))
+                .collect::<Vec<_>>();
+            let output = f(&inputs);
+            self.cases.push(TestCase::new(inputs, output));
+        }
+        self
+    }
+
+    pub fn build(&self) -> Result<TestSuite, SpecError> {
+        let arity = match self.cases.first() {
+            Some(case) => case.inputs.len(),
+            None => return Err(SpecError::Empty),
+        };
+        if arity == 0 {
+            return Err(SpecError::NoInputs);
+        }
+        if let Some(case) = self.cases.iter().position(|case| case.inputs.len() != arity) {
+            return Err(SpecError::InconsistentArity {
+                case,
+                expected: arity,
+                found: self.cases[case].inputs.len(),
+            });
+        }
+
+        Ok(TestSuite::new(self.cases.clone()))
+    }
+}
@@ -0,0 +1,291 @@
+use indexmap::IndexSet;
+use rayon::prelude::*;
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::iter;
+use std::sync::Arc;
+
+use arena::ExecutionArena;
+use encoding::EncodeError;
+use memo::MemoTable;
+use synthesizer::{SynthesisError, Synthesizer};
+use validate::ProgramValidationError;
+use {execute, par_frontier, Execution, OwnedExecution, Program, RegisterIndex, Value};
+
+// A breadth-first frontier that owns its programs instead of borrowing them
+// from an arena, so it can outlive the search and move between threads.
+// Its programs have the same number of instructions unless the search was
+// seeded with longer programs.
+#[derive(Debug, Clone)]
+pub struct SynthesizerState {
+    frontier: Arc<Vec<OwnedExecution>>,
+    generation: usize,
+}
+
+impl SynthesizerState {
+    pub(crate) fn from_frontier(frontier: &IndexSet<Execution>, generation: usize) -> SynthesizerState {
+        SynthesizerState {
+            frontier: Arc::new(frontier.iter().map(OwnedExecution::from).collect()),
+            generation,
+        }
+    }
+
+    pub fn frontier(&self) -> &[OwnedExecution] {
+        &self.frontier
+    }
+
+    // Generations searched to reach this frontier; 0 for the start
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    // The same programs run on `inputs` instead, merging any whose outputs
+    // now coincide
+    pub(crate) fn with_inputs(&self, inputs: &[Vec<Value>]) -> SynthesizerState {
+        let frontier = self
+            .frontier
+            .iter()
+            .map(|exe| OwnedExecution::replay(inputs.to_vec(), exe.instructions.clone()))
+            .collect::<IndexSet<_>>();
+        SynthesizerState {
+            frontier: Arc::new(frontier.into_iter().collect()),
+            generation: self.generation,
+        }
+    }
+
+    // The programs `keep` accepts, at the same generation
+    pub(crate) fn filtered(&self, keep: impl Fn(&OwnedExecution) -> bool) -> SynthesizerState {
+        SynthesizerState {
+            frontier: Arc::new(self.frontier.iter().filter(|exe| keep(exe)).cloned().collect()),
+            generation: self.generation,
+        }
+    }
+
+    // Replays the frontier into `arena`, one generation per instruction.
+    // Prefixes with equal outputs share a node, as they would in the search.
+    // Programs may differ in length, as with seeded programs; each one's
+    // last execution goes into the returned set.
+    pub(crate) fn rebuild<'a>(
+        &self,
+        arena: &'a ExecutionArena<'a>,
+        synthesizer: &Synthesizer,
+    ) -> &'a IndexSet<Execution<'a>> {
+        let depth = self.frontier.iter().map(|exe| exe.instructions.len()).max().unwrap_or(0);
+        let root = arena.alloc_generation(iter::once(synthesizer.root()).collect());
+        if depth == 0 {
+            return root;
+        }
+        let mut cursors = vec![root.iter().next().unwrap(); self.frontier.len()];
+
+        // Every prefix that is a parent, a generation at a time
+        for index in 0..depth - 1 {
+            let active = (0..self.frontier.len())
+                .filter(|&program| self.frontier[program].instructions.len() > index + 1)
+                .collect::<Vec<_>>();
+            let children = active
+                .iter()
+                .map(|&program| {
+                    execute(Program {
+                        parent: Some(cursors[program]),
+                        instruction: Some(self.frontier[program].instructions[index].clone()),
+                        depth: index + 1,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let outputs = children.iter().map(|child| child.output.clone()).collect::<Vec<_>>();
+
+            let level = arena.alloc_generation(children.into_iter().collect());
+            let by_output = level.iter().map(|exe| (&exe.output, exe)).collect::<HashMap<_, _>>();
+            for (&program, output) in active.iter().zip(&outputs) {
+                cursors[program] = by_output[output];
+            }
+        }
+
+        let last = self
+            .frontier
+            .iter()
+            .zip(&cursors)
+            .map(|(owned, &parent)| match owned.instructions.last() {
+                Some(inst) => execute(Program {
+                    parent: Some(parent),
+                    instruction: Some(inst.clone()),
+                    depth: owned.instructions.len(),
+                }),
+                None => synthesizer.root(),
+            })
+            .collect();
+        arena.alloc_generation(last)
+    }
+}
+
+// A frontier of one program, for resuming from a known partial solution.
+// Its generation is the program's length, which assumes no preamble.
+impl<'a> TryFrom<&'a OwnedExecution> for SynthesizerState {
+    type Error = ProgramValidationError;
+
+    fn try_from(exe: &'a OwnedExecution) -> Result<SynthesizerState, ProgramValidationError> {
+        let mut register_count = exe.inputs.first().map_or(0, Vec::len);
+        if register_count == 0 {
+            return Err(ProgramValidationError::MalformedRoot { depth: 0 });
+        }
+        for (index, inst) in exe.instructions.iter().enumerate() {
+            if !inst.is_valid_for(register_count) {
+                return Err(ProgramValidationError::InvalidRegister { depth: index + 1 });
+            }
+            register_count = inst.register_count_after(register_count);
+        }
+
+        Ok(SynthesizerState {
+            frontier: Arc::new(vec![exe.clone()]),
+            generation: exe.instructions.len(),
+        })
+    }
+}
+
+// What run_n_generations got to
+#[derive(Debug, Clone)]
+pub struct GenerationResult {
+    // Every solution in the last generation searched, in frontier order
+    pub solutions: Vec<(OwnedExecution, RegisterIndex)>,
+    pub frontier_size: usize,
+    pub generation: usize,
+    // A solution was found or there is nothing left to extend
+    pub done: bool,
+}
+
+impl Synthesizer {
+    // Advances breadth-first search by `n` generations from where the last
+    // call, run or restore left it, stopping early at a generation with a
+    // solution or an empty frontier. The new frontier is kept, so the next
+    // call or run continues from it. Generations are built as
+    // find_all_at_depth builds them: the builder's filters apply, but dedup
+    // lookback, pruning, limits and monitors do not.
+    pub fn run_n_generations(&mut self, n: usize) -> GenerationResult {
+        let (result, state) = self.config.install(|| self.advance(n));
+        self.stepped_frontier = state.frontier().iter().cloned().collect();
+        self.stepped_generation = state.generation();
+        result
+    }
+
+    fn advance(&self, n: usize) -> (GenerationResult, SynthesizerState) {
+        let state = self.snapshot();
+        let arena = ExecutionArena::new();
+        let memo = MemoTable::new();
+        let mut frontier = state.rebuild(&arena, self);
+        let mut generation = state.generation();
+
+        for _ in 0..n {
+            if frontier.is_empty() || par_frontier(frontier).any(|exe| self.output_register(exe).is_some()) {
+                break;
+            }
+            let mut next = self.next_generation(frontier, &memo);
+            if generation == 0 {
+                next.extend(self.seeds(&arena, frontier.iter().next().unwrap()));
+            }
+            frontier = arena.alloc_generation(next);
+            generation += 1;
+        }
+
+        let solutions = self.solutions(frontier);
+        let state = SynthesizerState::from_frontier(frontier, generation);
+        self.restore(state.clone());
+        let result = GenerationResult {
+            done: !solutions.is_empty() || frontier.is_empty(),
+            solutions,
+            frontier_size: frontier.len(),
+            generation,
+        };
+        (result, state)
+    }
+
+    // The frontier the last run_n_generations left; empty before the first
+    // call. It is a copy taken when that call returned: run and restore
+    // move the search on without changing it, so after them it is stale
+    // until the next run_n_generations. snapshot has the frontier the next
+    // search starts from, in search order.
+    pub fn current_frontier(&self) -> &HashSet<OwnedExecution> {
+        &self.stepped_frontier
+    }
+
+    // The generation of current_frontier, with the same staleness
+    pub fn current_generation(&self) -> usize {
+        self.stepped_generation
+    }
+
+    fn solutions(&self, frontier: &IndexSet<Execution>) -> Vec<(OwnedExecution, RegisterIndex)> {
+        par_frontier(frontier)
+            .filter_map(|exe| self.output_register(exe).map(|reg| (OwnedExecution::from(exe), reg)))
+            .collect()
+    }
+
+    // Where the last unseeded search stopped without a solution, or the
+    // start if it has not stopped yet
+    pub fn snapshot(&self) -> SynthesizerState {
+        if let Some(ref state) = *self.state.lock().unwrap() {
+            return state.clone();
+        }
+        let arena = ExecutionArena::new();
+        let start = self.start(&arena);
+        SynthesizerState {
+            frontier: Arc::new(vec![OwnedExecution::from(&start)]),
+            generation: 0,
+        }
+    }
+
+    // The next unseeded search continues from `state` instead of the start.
+    // `state` should come from a synthesizer with the same spec.
+    pub fn restore(&self, state: SynthesizerState) {
+        *self.state.lock().unwrap() = Some(state);
+    }
+
+    // The next unseeded search continues from `initial_frontier` instead of
+    // the start, as if those programs were its last generation. They are
+    // rerun on this synthesizer's spec, so they may come from a
+    // checkpoint, a session or another worker, but they must all have the
+    // spec's number of inputs and the same length. As with restoring from
+    // one program, that length is taken as the generation. The programs are
+    // taken in order of their encoding, so the frontier, and which
+    // solution is found first, does not depend on the set's hashing, and
+    // each must be one the encoder can write.
+    pub fn warm_start(self, initial_frontier: HashSet<OwnedExecution>) -> Result<Synthesizer, SynthesisError> {
+        let arity = match self.inputs.first() {
+            Some(inputs) => inputs.len(),
+            None => return Err(SynthesisError::EmptySpec),
+        };
+        let mut programs = initial_frontier
+            .into_iter()
+            .map(|exe| Ok((self.encode_program(&exe.instructions)?, exe)))
+            .collect::<Result<Vec<_>, EncodeError>>()
+            .map_err(SynthesisError::FrontierEncoding)?;
+        programs.sort_by(|a, b| a.0.cmp(&b.0));
+        let programs = programs.into_iter().map(|(_, exe)| exe).collect::<Vec<_>>();
+        let depth = match programs.first() {
+            Some(exe) => exe.instructions.len(),
+            None => return Err(SynthesisError::EmptyFrontier),
+        };
+
+        let mut frontier = IndexSet::new();
+        for exe in programs {
+            let found = exe.inputs.first().map_or(0, Vec::len);
+            if found != arity {
+                return Err(SynthesisError::FrontierArity { expected: arity, found });
+            }
+            if exe.instructions.len() != depth {
+                return Err(SynthesisError::FrontierDepth {
+                    expected: depth,
+                    found: exe.instructions.len(),
+                });
+            }
+            let replayed = OwnedExecution::try_replay(self.inputs.clone(), exe.instructions)
+                .ok_or(SynthesisError::InvalidFrontierProgram)?;
+            frontier.insert(replayed);
+        }
+
+        self.restore(SynthesizerState {
+            frontier: Arc::new(frontier.into_iter().collect()),
+            generation: depth,
+        });
+        Ok(self)
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use {Execution, Instruction, OwnedExecution, Value};
+
+fn root_hash(inputs: &[Vec<Value>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    inputs.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn step_hash(parent: u64, inst: &Instruction) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    parent.hash(&mut hasher);
+    inst.hash(&mut hasher);
+    hasher.finish()
+}
+
+// A Merkle-style hash: the root hashes the inputs, and each instruction
+// hashes its parent's hash together with itself, so programs that agree
+// on their inputs and a prefix agree on the hash of that prefix. The
+// hasher is fixed, but it is only stable within one build of the crate.
+pub fn hash_program(exe: &Execution) -> u64 {
+    exe.instructions().fold(root_hash(exe.inputs()), step_hash)
+}
+
+// One entry of a ProgramStore: the inputs at the root, and otherwise the
+// last instruction on top of the program with hash `parent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Root(Vec<Vec<Value>>),
+    Step { parent: u64, instruction: Instruction },
+}
+
+// Programs out of the arena, one node per prefix keyed by hash_program, so
+// programs with a common prefix store it once
+#[derive(Debug, Clone, Default)]
+pub struct ProgramStore {
+    nodes: HashMap<u64, Arc<Node>>,
+}
+
+impl ProgramStore {
+    pub fn new() -> ProgramStore {
+        ProgramStore::default()
+    }
+
+    // Stores every prefix of `exe` not already stored and returns its hash.
+    // None if some prefix's hash belongs to a different stored node.
+    pub fn insert(&mut self, exe: &Execution) -> Option<u64> {
+        let mut hash = root_hash(exe.inputs());
+        self.insert_node(hash, Node::Root(exe.inputs().clone()))?;
+        for inst in exe.instructions() {
+            let parent = hash;
+            hash = step_hash(parent, inst);
+            self.insert_node(
+                hash,
+                Node::Step {
+                    parent,
+                    instruction: inst.clone(),
+                },
+            )?;
+        }
+        Some(hash)
+    }
+
+    fn insert_node(&mut self, hash: u64, node: Node) -> Option<()> {
+        let stored = self.nodes.entry(hash).or_insert_with(|| Arc::new(node.clone()));
+        if **stored == node {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    pub fn node(&self, hash: u64) -> Option<Arc<Node>> {
+        self.nodes.get(&hash).cloned()
+    }
+
+    // The program with hash `hash`, replayed from its nodes
+    pub fn get(&self, hash: u64) -> Option<OwnedExecution> {
+        let mut instructions = Vec::new();
+        let mut current = hash;
+        loop {
+            match *self.nodes.get(&current)?.as_ref() {
+                Node::Root(ref inputs) => {
+                    instructions.reverse();
+                    return Some(OwnedExecution::replay(inputs.clone(), instructions));
+                }
+                Node::Step { parent, ref instruction } => {
+                    instructions.push(instruction.clone());
+                    current = parent;
+                }
+            }
+        }
+    }
+
+    // Distinct prefixes stored, roots included
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_program, Node, ProgramStore};
+    use arena::ExecutionArena;
+    use {Execution, Instruction, Register};
+
+    // add r0 r0 followed by either mul r0 r0 or neg r0 shares the root and
+    // the add
+    #[test]
+    fn programs_share_prefix_nodes() {
+        let arena = ExecutionArena::new();
+        let root = arena.alloc_generation(vec![Execution::root(vec![vec![3], vec![-1]])].into_iter().collect());
+        let r0 = Register::new(0);
+        let double = root.iter().next().unwrap().derive(Instruction::Add(r0, r0), &arena);
+        let square = double.derive(Instruction::Mul(r0, r0), &arena);
+        let negate = double.derive(Instruction::Neg(r0), &arena);
+
+        let mut store = ProgramStore::new();
+        let square_hash = store.insert(square).unwrap();
+        let negate_hash = store.insert(negate).unwrap();
+        assert_eq!(store.insert(square), Some(square_hash));
+        assert_eq!(store.len(), 4);
+
+        let parent = |hash| match *store.node(hash).unwrap() {
+            Node::Step { parent, .. } => parent,
+            Node::Root(..) => panic!("a program with instructions is not a root"),
+        };
+        assert_eq!(parent(square_hash), hash_program(double));
+        assert_eq!(parent(negate_hash), hash_program(double));
+
+        let stored = store.get(negate_hash).unwrap();
+        assert_eq!(stored.instructions, negate.linearize());
+        assert_eq!(stored.output, negate.output);
+    }
+}
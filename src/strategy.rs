@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use testsuite::TestSuite;
+use {OwnedExecution, RegisterIndex};
+
+// Shared flag telling a running search to give up. A child token is
+// cancelled whenever its parent is, but cancelling it leaves the parent alone.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    parent: Option<Box<CancellationToken>>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn child(&self) -> CancellationToken {
+        CancellationToken {
+            flag: Arc::new(AtomicBool::new(false)),
+            parent: Some(Box::new(self.clone())),
+            deadline: None,
+        }
+    }
+
+    // A child that also counts as cancelled from `deadline` on
+    pub fn child_with_deadline(&self, deadline: Instant) -> CancellationToken {
+        CancellationToken {
+            deadline: Some(deadline),
+            ..self.child()
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+            || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+}
+
+pub trait SearchStrategy: Send + Sync {
+    // Returns None if the search was cancelled or gave up without a solution
+    fn run(
+        &self,
+        spec: &TestSuite,
+        cancel: CancellationToken,
+    ) -> Option<(OwnedExecution, RegisterIndex)>;
+}
@@ -0,0 +1,1428 @@
+use rayon::prelude::*;
+use rayon::{Configuration, ThreadPool};
+
+use rand::rngs::SmallRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+use indexmap::IndexSet;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::iter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use arena::{execution_bytes, ExecutionArena};
+use dedup::{verify_dedup_soundness, DEDUP_CHECK_LIMIT};
+use encoding::{DecodeError, EncodeError, FixedWidthEncoder, InstructionEncoder};
+use estimate::GenerationStats;
+use fusion::FusedInstruction;
+use genetic::GASearchStrategy;
+use histogram::HistogramPruner;
+use interval::{in_domain, input_intervals, leaves_domain, Interval};
+use memo::MemoTable;
+use monitor::{SynthesizerMonitor, TerminationReason};
+use mutation::MutationSynthesizer;
+use printer::{OutputFormat, ProgramPrinter};
+use progress::SearchProgress;
+use rng::{SearchRng, SharedRng};
+use sampling::RandomSamplingStrategy;
+use state::SynthesizerState;
+use strategy::{CancellationToken, SearchStrategy};
+use testsuite::{Domain, TestSuite, UNBOUNDED};
+
+use {add_one_instruction, add_one_instruction_shuffled, check_pattern, collect_frontier, collect_ordered, execute};
+use {execute_with, OverflowPolicy};
+use {is_canonical_ordering, is_mov_round_trip, par_frontier, passing_weight, score};
+use {register_matches, register_matches_par};
+use {Execution, Instruction, InstructionVariant, OwnedExecution, Program, Register, RegisterIndex, Value};
+use {ALL_INSTRUCTION_VARIANTS, MAX_REGISTERS};
+use VerificationPolicy;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SynthesisError {
+    EmptySpec,
+    NoInputRegisters,
+    // Test case `case` has a different number of inputs than the first one
+    InconsistentArity { case: usize, expected: usize, found: usize },
+    // A different number of expected outputs than there are test cases
+    OutputCount { expected: usize, found: usize },
+    // More test cases than a search over packed truth tables can hold
+    TooManyCases { limit: usize, found: usize },
+    // A domain for an input that does not exist, or with lo above hi
+    InvalidDomain { input: usize },
+    // Test case `case` has a value outside the declared domain of `input`
+    OutOfDomain { case: usize, input: usize },
+    // Preamble instruction `index` reads or writes a register that does not
+    // exist yet
+    InvalidPreamble { index: usize },
+    // Every program in the frontier already uses max_registers registers
+    RegisterCapReached { generation: usize },
+    // find_with_oracle ran out of programs that pass the test cases
+    NoProgramFound { rounds: usize },
+    // The oracle rejected a program without an input that it gets wrong
+    MissingCounterexample { round: usize },
+    // with_test_case_weights gave a different number of weights than there
+    // are test cases
+    WeightCount { expected: usize, found: usize },
+    // warm_start was given no programs
+    EmptyFrontier,
+    // A warm_start program has a different number of inputs than the spec
+    FrontierArity { expected: usize, found: usize },
+    // A warm_start program has a different length than the first one
+    FrontierDepth { expected: usize, found: usize },
+    // A warm_start program reads a register that does not exist yet
+    InvalidFrontierProgram,
+    // The encoder cannot write a warm_start program, so it has no place in
+    // the frontier's order
+    FrontierEncoding(EncodeError),
+    // No way of filling a HoledProgram's holes satisfies the spec
+    NoFilling,
+    // Breadth-first search reached with_generation_limit, leaving a
+    // frontier of `frontier_size` programs
+    GenerationLimitExceeded { generations_run: usize, frontier_size: usize },
+    // A breadth-first generation had more programs than
+    // with_frontier_size_limit allows
+    FrontierLimitExceeded { size: usize },
+    // run_with_logging could not write to its log
+    LogWrite(io::ErrorKind),
+}
+
+impl fmt::Display for SynthesisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SynthesisError::EmptySpec => write!(f, "the spec has no test cases"),
+            SynthesisError::NoInputRegisters => write!(f, "the test cases have no inputs"),
+            SynthesisError::InconsistentArity { case, expected, found } => write!(
+                f,
+                "test case {} has {} inputs, expected {}",
+                case, found, expected
+            ),
+            SynthesisError::OutputCount { expected, found } => {
+                write!(f, "{} expected outputs for {} test cases", found, expected)
+            }
+            SynthesisError::TooManyCases { limit, found } => {
+                write!(f, "{} test cases, at most {} are supported", found, limit)
+            }
+            SynthesisError::InvalidDomain { input } => write!(f, "invalid domain for input {}", input),
+            SynthesisError::OutOfDomain { case, input } => {
+                write!(f, "test case {} has input {} outside its domain", case, input)
+            }
+            SynthesisError::InvalidPreamble { index } => {
+                write!(f, "preamble instruction {} uses a register that does not exist", index)
+            }
+            SynthesisError::RegisterCapReached { generation } => {
+                write!(f, "every program hit the register cap by generation {}", generation)
+            }
+            SynthesisError::NoProgramFound { rounds } => {
+                write!(f, "no program passes the test cases after {} oracle rounds", rounds)
+            }
+            SynthesisError::MissingCounterexample { round } => {
+                write!(f, "in round {} the oracle rejected a program but gave no input it fails", round)
+            }
+            SynthesisError::WeightCount { expected, found } => {
+                write!(f, "{} test case weights for {} test cases", found, expected)
+            }
+            SynthesisError::EmptyFrontier => write!(f, "the starting frontier has no programs"),
+            SynthesisError::FrontierArity { expected, found } => {
+                write!(f, "a starting program has {} inputs, expected {}", found, expected)
+            }
+            SynthesisError::FrontierDepth { expected, found } => {
+                write!(f, "a starting program has {} instructions, expected {}", found, expected)
+            }
+            SynthesisError::InvalidFrontierProgram => {
+                write!(f, "a starting program uses a register that does not exist")
+            }
+            SynthesisError::FrontierEncoding(ref e) => write!(f, "a starting program cannot be encoded: {}", e),
+            SynthesisError::NoFilling => write!(f, "no filling of the holes satisfies the spec"),
+            SynthesisError::GenerationLimitExceeded {
+                generations_run,
+                frontier_size,
+            } => write!(
+                f,
+                "no program found in {} generations, {} programs left to extend",
+                generations_run, frontier_size
+            ),
+            SynthesisError::FrontierLimitExceeded { size } => {
+                write!(f, "a generation of {} programs is over the frontier size limit", size)
+            }
+            SynthesisError::LogWrite(kind) => write!(f, "could not write the search log: {}", kind),
+        }
+    }
+}
+
+impl Error for SynthesisError {}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum Strategy {
+    #[default]
+    BreadthFirst,
+    GeneticAlgorithm { population: usize, generations: usize },
+    // See RandomSamplingStrategy; it stops after max_depth when there is one
+    RandomSampling { samples_per_gen: usize },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SynthesizerBuilder {
+    strategy: Strategy,
+    restart_seeds: Vec<u64>,
+    max_depth: Option<usize>,
+    generation_limit: Option<usize>,
+    frontier_size_limit: Option<usize>,
+    refinement: Option<MutationSynthesizer>,
+    preamble: Vec<Instruction>,
+    forbidden_patterns: Vec<Vec<Instruction>>,
+    symmetry_breaking: bool,
+    skip_mov_round_trips: bool,
+    rng: Option<SharedRng>,
+    max_registers: Option<usize>,
+    grow_registers: bool,
+    encoder: Option<Arc<dyn InstructionEncoder>>,
+    printer: Option<Arc<dyn ProgramPrinter>>,
+    output: Option<SolutionOutput>,
+    progress_bar: bool,
+    enabled_instructions: Option<Vec<InstructionVariant>>,
+    dedup_lookback: usize,
+    pub(crate) beam_width: Option<usize>,
+    pub(crate) output_register_hint: Option<RegisterIndex>,
+    threads: Option<usize>,
+    rayon_pool: Option<Arc<ThreadPool>>,
+    timeout: Option<Duration>,
+    // Also save the frontier when a solution is found, for SynthesizerSession
+    pub(crate) resumable: bool,
+    parallel_dedup: bool,
+    memoize_execute: bool,
+    monitors: Vec<Arc<dyn SynthesizerMonitor>>,
+    verification: VerificationPolicy,
+    pub(crate) overflow: OverflowPolicy,
+    test_case_weights: Vec<f64>,
+    parallel_verify: bool,
+    value_domain: Option<Domain>,
+    weight_threshold: f64,
+    seeded_programs: Vec<Vec<Instruction>>,
+    instruction_limits: HashMap<InstructionVariant, usize>,
+    histogram_pruning: Option<(f64, usize)>,
+    fused_atoms: Vec<FusedInstruction>,
+    input_preprocessing: Option<InputPreprocessing>,
+    initial_transform: Option<InitialTransform>,
+    register_file_init: Vec<Value>,
+    output_postprocessing: Option<OutputPostprocessing>,
+}
+
+// Rewrites one test case's inputs into the register file a search starts from
+#[derive(Clone)]
+struct InitialTransform(Arc<dyn Fn(Vec<Value>) -> Vec<Value> + Send + Sync>);
+
+impl fmt::Debug for InitialTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("InitialTransform")
+    }
+}
+
+type Preprocess = dyn Fn(&mut Vec<Value>) + Send + Sync;
+
+// Normalizes one test case's inputs in place before anything else sees them
+#[derive(Clone)]
+struct InputPreprocessing(Arc<Preprocess>);
+
+impl fmt::Debug for InputPreprocessing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("InputPreprocessing")
+    }
+}
+
+// Maps register values and targets into the space they are compared in
+#[derive(Clone)]
+struct OutputPostprocessing(Arc<dyn Fn(Value) -> Value + Send + Sync>);
+
+impl fmt::Debug for OutputPostprocessing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("OutputPostprocessing")
+    }
+}
+
+// Where printed solutions go instead of stdout
+#[derive(Clone)]
+struct SolutionOutput(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl fmt::Debug for SolutionOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SolutionOutput")
+    }
+}
+
+pub type SynthesisResult = Result<Option<(OwnedExecution, RegisterIndex)>, SynthesisError>;
+
+impl SynthesizerBuilder {
+    pub fn new() -> SynthesizerBuilder {
+        SynthesizerBuilder::default()
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    // Run `n_restarts` independent searches, each generating instructions in
+    // its own shuffled order, and keep whichever solution turns up first.
+    // Which restart that is can differ between runs with several threads.
+    pub fn with_random_restarts(mut self, n_restarts: usize, rng: &mut impl Rng) -> Self {
+        self.restart_seeds = (0..n_restarts).map(|_| rng.gen()).collect();
+        self
+    }
+
+    // Stop breadth-first expansion after `max_depth` instructions
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    // Fail with GenerationLimitExceeded once breadth-first search has
+    // searched `generations` generations without a solution. Unlike
+    // max_depth, running out is an error and nothing is refined.
+    pub fn with_generation_limit(mut self, generations: usize) -> Self {
+        self.generation_limit = Some(generations);
+        self
+    }
+
+    // Fail with FrontierLimitExceeded instead of extending a breadth-first
+    // generation of more than `size` programs, to give up on
+    // underconstrained specs before running out of memory. A solution in
+    // that generation is still returned.
+    pub fn with_frontier_size_limit(mut self, size: usize) -> Self {
+        self.frontier_size_limit = Some(size);
+        self
+    }
+
+    // Hand the final frontier to `refiner` if BFS runs out of depth
+    pub fn with_mutation_refinement(mut self, refiner: MutationSynthesizer) -> Self {
+        self.refinement = Some(refiner);
+        self
+    }
+
+    // Start every search from the inputs with `instructions` already applied,
+    // so depth limits only count the instructions found after them
+    pub fn with_preamble(mut self, instructions: Vec<Instruction>) -> Self {
+        self.preamble = instructions;
+        self
+    }
+
+    // Never generate a program with more than `max_count` instructions of
+    // `variant`, counting any preamble, as for hardware with few
+    // multipliers. A later limit for the same variant replaces this one.
+    pub fn with_instruction_limit(mut self, variant: InstructionVariant, max_count: usize) -> Self {
+        self.instruction_limits.insert(variant, max_count);
+        self
+    }
+
+    // Rewrite each test case's inputs with `f` when building, as if the spec
+    // had been written that way, for normalizing like sorting or masking.
+    // Unlike an initial transform this changes the spec itself, so domains
+    // and OwnedExecution::evaluate work on the preprocessed inputs. Each
+    // rewrite is logged at debug level.
+    pub fn with_input_preprocessing(mut self, f: impl Fn(&mut Vec<Value>) + Send + Sync + 'static) -> Self {
+        self.input_preprocessing = Some(InputPreprocessing(Arc::new(f)));
+        self
+    }
+
+    // Start breadth-first search from `f` applied to each test case's
+    // inputs instead of the inputs themselves, for example to append
+    // constant registers or split an input into parts. Every test case
+    // must come out the same length. Solutions and their
+    // OwnedExecution::evaluate work on the transformed register files.
+    // Domains are still checked against the inputs but no longer prune.
+    pub fn with_initial_transform(mut self, f: impl Fn(Vec<Value>) -> Vec<Value> + Send + Sync + 'static) -> Self {
+        self.initial_transform = Some(InitialTransform(Arc::new(f)));
+        self
+    }
+
+    // Count a register as holding the result when `f` of its value equals
+    // `f` of the target on every case, for targets in a normalized space
+    // such as wrapping arithmetic with `|v| v.rem_euclid(256)`. Targets are
+    // mapped once when building. Only the breadth-first solution check
+    // applies `f`; scores and the other strategies still compare raw
+    // register values with the mapped targets.
+    pub fn with_output_postprocessing(mut self, f: impl Fn(Value) -> Value + Send + Sync + 'static) -> Self {
+        self.output_postprocessing = Some(OutputPostprocessing(Arc::new(f)));
+        self
+    }
+
+    // Start every test case with `init` appended to its register file, after
+    // any initial transform, so common constants like 0, 1 and -1 are there
+    // without spending depth on making them. As with an initial transform,
+    // OwnedExecution::evaluate wants the constants after the inputs.
+    pub fn with_register_file_init(mut self, init: Vec<Value>) -> Self {
+        self.register_file_init = init;
+        self
+    }
+
+    // Add these programs, run after any preamble, to the first generation
+    // next to its one-instruction programs, so the search extends them
+    // from the start. Seeds that read a missing register or change nothing
+    // are dropped. A longer seed means a solution built on it may be
+    // longer than the shortest one.
+    pub fn with_seeded_programs(mut self, seeds: Vec<Vec<Instruction>>) -> Self {
+        self.seeded_programs = seeds;
+        self
+    }
+
+    // Also generate each of `atoms` as a single instruction wherever its
+    // registers exist, as from FusionPass, so programs built from them
+    // are found at a smaller depth. The other options see them as
+    // InstructionVariant::Fused. Programs using them can only be encoded
+    // with VarIntEncoder.
+    pub fn with_fused_atoms(mut self, atoms: Vec<FusedInstruction>) -> Self {
+        assert!(atoms.iter().all(|atom| !atom.0.is_empty()), "an empty fused instruction would change nothing");
+        self.fused_atoms = atoms;
+        self
+    }
+
+    // Never generate a program ending in one of `patterns`, such as a
+    // `neg r0` straight after another
+    pub fn with_forbidden_patterns(mut self, patterns: Vec<Vec<Instruction>>) -> Self {
+        assert!(patterns.iter().all(|pattern| !pattern.is_empty()), "an empty pattern would forbid every program");
+        self.forbidden_patterns = patterns;
+        self
+    }
+
+    // Generate only one order of each Add and Mul (see
+    // is_canonical_ordering). Shrinks the frontier at the cost of possibly
+    // missing the shortest program.
+    pub fn with_symmetry_breaking(mut self, symmetry_breaking: bool) -> Self {
+        self.symmetry_breaking = symmetry_breaking;
+        self
+    }
+
+    // Symmetry breaking, and also never generate a mov that copies back the
+    // mov before it (see is_mov_round_trip). The round trip changes
+    // nothing, so that part only saves checking the child.
+    pub fn disable_equivalent_instruction_pairs(mut self) -> Self {
+        self.symmetry_breaking = true;
+        self.skip_mov_round_trips = true;
+        self
+    }
+
+    // Draw every random choice (GA, refinement) from `rng` instead of the
+    // thread's generator
+    pub fn with_rng(mut self, rng: Box<dyn RngCore + Send>) -> Self {
+        self.rng = Some(SharedRng::new(rng));
+        self
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.with_rng(Box::new(SmallRng::seed_from_u64(seed)))
+    }
+
+    // Stop generating `mov` to a new register once a program has
+    // `max_registers` of them. A search whose whole frontier is at the cap
+    // fails with RegisterCapReached instead of carrying on.
+    pub fn with_max_registers(mut self, max_registers: usize) -> Self {
+        self.max_registers = Some(max_registers);
+        self
+    }
+
+    // Ignore max_registers and let programs keep adding registers
+    pub fn with_grow_registers(mut self, grow_registers: bool) -> Self {
+        self.grow_registers = grow_registers;
+        self
+    }
+
+    // The byte format encode_program and decode_program use, fixed-width
+    // by default
+    pub fn with_encoder(mut self, encoder: impl InstructionEncoder + 'static) -> Self {
+        self.encoder = Some(Arc::new(encoder));
+        self
+    }
+
+    // Write each breadth-first solution to stdout with `printer` as soon as
+    // it is found
+    pub fn with_output_printer(mut self, printer: Box<dyn ProgramPrinter>) -> Self {
+        self.printer = Some(Arc::from(printer));
+        self
+    }
+
+    // with_output_printer for one of the built-in formats
+    pub fn with_output_format_for_solution(mut self, fmt: OutputFormat) -> Self {
+        self.printer = Some(Arc::new(fmt));
+        self
+    }
+
+    // Print solutions to `writer` instead of stdout. Nothing is printed
+    // unless a printer or format is set too.
+    pub fn with_output(mut self, writer: Box<dyn Write + Send>) -> Self {
+        self.output = Some(SolutionOutput(Arc::new(Mutex::new(writer))));
+        self
+    }
+
+    // Show generation, frontier size, elapsed time and programs per second
+    // on stderr while searching. Has no effect without the `indicatif`
+    // feature.
+    pub fn with_progress_bar(mut self, progress_bar: bool) -> Self {
+        self.progress_bar = progress_bar;
+        self
+    }
+
+    // Only generate instructions of these variants. The preamble may still
+    // use any.
+    pub fn with_enabled_instructions(mut self, variants: Vec<InstructionVariant>) -> Self {
+        self.enabled_instructions = Some(variants);
+        self
+    }
+
+    // The variants breadth-first search will generate: the enabled ones, or
+    // else all of ALL_INSTRUCTION_VARIANTS, plus Fused if there are fused
+    // atoms and it is not disabled. Instruction limits are not counted.
+    pub fn enabled_instructions(&self) -> Vec<InstructionVariant> {
+        let mut variants = ALL_INSTRUCTION_VARIANTS.to_vec();
+        if !self.fused_atoms.is_empty() {
+            variants.push(InstructionVariant::Fused);
+        }
+        if let Some(ref enabled) = self.enabled_instructions {
+            variants.retain(|variant| enabled.contains(variant));
+        }
+        variants
+    }
+
+    // How many programs add_one_instruction makes from a parent with
+    // `register_count` registers: a mov to a new register and a neg per
+    // register, and a mov, add and mul per ordered pair. At MAX_REGISTERS
+    // there is no new register to mov to.
+    pub fn instruction_count_bound(register_count: usize) -> usize {
+        let new_register_movs = if register_count < MAX_REGISTERS { register_count } else { 0 };
+        new_register_movs + register_count + register_count * register_count * 3
+    }
+
+    // Also drop new executions whose output matches one from the last
+    // `generations` generations, which a shorter program already reached
+    pub fn with_dedup_lookback(mut self, generations: usize) -> Self {
+        self.dedup_lookback = generations;
+        self
+    }
+
+    // Executions kept per generation by run_approximate
+    pub fn with_beam_width(mut self, beam_width: usize) -> Self {
+        assert!(beam_width > 0, "a beam must keep at least one execution");
+        self.beam_width = Some(beam_width);
+        self
+    }
+
+    // Run on a dedicated pool of `threads` threads instead of rayon's global
+    // one
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    // Run every parallel part of synthesis on `pool` instead of rayon's
+    // global one, so synthesizers serving concurrent requests can share
+    // threads without oversubscribing. Takes precedence over with_threads.
+    pub fn with_rayon_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.rayon_pool = Some(pool);
+        self
+    }
+
+    // Runs `op` on the pool from with_rayon_pool, if there is one
+    pub(crate) fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match self.rayon_pool {
+            Some(ref pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    // Give up, as if cancelled, once a run has taken `timeout`. Checked
+    // between generations, so a long generation can overrun it.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // Deduplicate each generation with collect_frontier's fold-and-union
+    // instead of rayon's collect. May pay off with many cores; on one it
+    // takes about twice as long.
+    pub fn with_parallel_dedup(mut self, parallel_dedup: bool) -> Self {
+        self.parallel_dedup = parallel_dedup;
+        self
+    }
+
+    // Look up each breadth-first child in a MemoTable before executing it.
+    // The table lasts for one search and keeps an output matrix per
+    // distinct parent output and instruction, so it costs memory on the
+    // order of everything the search has run.
+    pub fn with_execute_memo(mut self, memoize_execute: bool) -> Self {
+        self.memoize_execute = memoize_execute;
+        self
+    }
+
+    // A heuristic for breadth-first search: once one kind of instruction
+    // has been the last instruction of more than `threshold` of the
+    // frontier for `generations` generations in a row without a solution,
+    // stop generating it for the rest of the search. This can lose the
+    // shortest solution, or every solution.
+    pub fn with_instruction_histogram_pruning(mut self, threshold: f64, generations: usize) -> Self {
+        assert!((0.0..1.0).contains(&threshold), "the threshold must be a fraction below 1");
+        assert!(generations > 0, "pruning needs at least one generation to go on");
+        self.histogram_pruning = Some((threshold, generations));
+        self
+    }
+
+    // Report each breadth-first generation, solution and stop to `monitor`,
+    // after any monitors added before it
+    pub fn with_monitor(mut self, monitor: Arc<dyn SynthesizerMonitor>) -> Self {
+        self.monitors.push(monitor);
+        self
+    }
+
+    // Where the result probably is, by convention or a hunch. Under
+    // VerificationPolicy::AnyRegister, breadth-first search checks `reg`
+    // first and prefers a solution with the result there to an equally
+    // short one with it elsewhere. run_approximate breaks ties between
+    // executions satisfying as many cases by how many target values `reg`
+    // holds.
+    pub fn with_output_register_hint(mut self, reg: RegisterIndex) -> Self {
+        self.output_register_hint = Some(reg);
+        self
+    }
+
+    // Compare a register against the test cases in parallel when checking
+    // breadth-first programs for a solution. The check already runs in
+    // parallel over the frontier, and rayon nests the two on the same
+    // pool. On one core it neither helps nor hurts much at up to 500 cases.
+    pub fn with_parallel_verify(mut self, parallel_verify: bool) -> Self {
+        self.parallel_verify = parallel_verify;
+        self
+    }
+
+    // Drop programs in breadth-first search that leave any register outside
+    // [lo, hi] on any test case, as when values stand for bytes. Inputs are
+    // registers too, so they should be in range.
+    pub fn with_value_domain(mut self, lo: Value, hi: Value) -> Self {
+        assert!(lo <= hi, "the value domain is empty");
+        self.value_domain = Some((lo, hi));
+        self
+    }
+
+    // Accept a register in breadth-first search when the test cases it
+    // passes carry at least `threshold` of the total weight, one weight
+    // per test case, instead of only when it passes all of them. See
+    // weighted_verify. The chosen register still follows the verification
+    // policy. Other strategies and refinement still want every case.
+    pub fn with_test_case_weights(mut self, weights: Vec<f64>, threshold: f64) -> Self {
+        assert!(
+            weights.iter().all(|&weight| weight.is_finite() && weight >= 0.0) && weights.iter().sum::<f64>() > 0.0,
+            "weights must be finite, non-negative and not all zero"
+        );
+        self.test_case_weights = weights;
+        self.weight_threshold = threshold;
+        self
+    }
+
+    // How generated instructions treat results too large for a Value in
+    // breadth-first search. Under Checked, programs that overflow on any
+    // test case are dropped. Only the search follows the policy: the
+    // preamble, the other strategies and OwnedExecution's replay and
+    // evaluate still wrap. See OverflowPolicy::Undefined before using it.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    // Which registers breadth-first search accepts the result in. Other
+    // strategies and refinement still accept any register.
+    pub fn with_verification_policy(mut self, policy: VerificationPolicy) -> Self {
+        self.verification = policy;
+        self
+    }
+
+    pub fn build(&self, spec: &TestSuite) -> Synthesizer {
+        let mut spec = spec.clone();
+        if let Some(ref preprocess) = self.input_preprocessing {
+            for case in &mut spec.cases {
+                let before = case.inputs.clone();
+                (preprocess.0)(&mut case.inputs);
+                search_log!(debug, "preprocessed inputs {:?} to {:?}", before, case.inputs);
+            }
+        }
+
+        let mut inputs = match self.initial_transform {
+            Some(ref transform) => spec.inputs().into_iter().map(|row| (transform.0)(row)).collect(),
+            None => spec.inputs(),
+        };
+        for row in &mut inputs {
+            row.extend_from_slice(&self.register_file_init);
+        }
+        let mut outputs = spec.outputs();
+        if let Some(ref postprocess) = self.output_postprocessing {
+            for output in &mut outputs {
+                *output = (postprocess.0)(*output);
+            }
+        }
+
+        // Domains describe the inputs, not the transformed registers.
+        // Constant registers are their own domain, and the outputs must be
+        // reachable however far they are from the inputs.
+        let arity = inputs.first().map_or(0, |row| row.len() - self.register_file_init.len());
+        let domains = (0..arity)
+            .map(|input| spec.domain(input))
+            .chain(self.register_file_init.iter().map(|&value| (value, value)))
+            .collect::<Vec<_>>();
+        let domain_bounds = if spec.domains.is_empty() || self.initial_transform.is_some() || domains.contains(&UNBOUNDED) {
+            None
+        } else {
+            let lo = domains.iter().map(|domain| domain.0).chain(outputs.iter().cloned()).min();
+            let hi = domains.iter().map(|domain| domain.1).chain(outputs.iter().cloned()).max();
+            lo.zip(hi)
+        };
+        let root_intervals = if domain_bounds.is_some() { input_intervals(&domains) } else { vec![] };
+
+        Synthesizer {
+            config: self.clone(),
+            outputs,
+            spec,
+            inputs,
+            domain_bounds,
+            root_intervals,
+            generation_stats: Mutex::new(Vec::new()),
+            state: Mutex::new(None),
+            stepped_frontier: HashSet::new(),
+            stepped_generation: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Synthesizer {
+    pub(crate) config: SynthesizerBuilder,
+    pub(crate) spec: TestSuite,
+    // The spec split into the layout the search works on
+    pub(crate) inputs: Vec<Vec<Value>>,
+    pub(crate) outputs: Vec<Value>,
+    // Where every value the search needs must lie when the spec declares
+    // a domain for each input, with the registers' starting intervals
+    pub(crate) domain_bounds: Option<Domain>,
+    pub(crate) root_intervals: Vec<Interval>,
+    // Filled in by the unseeded search of the last run
+    pub(crate) generation_stats: Mutex<Vec<GenerationStats>>,
+    // Where the next unseeded search starts, if not from the beginning
+    pub(crate) state: Mutex<Option<SynthesizerState>>,
+    // What the last run_n_generations left, for current_frontier and
+    // current_generation
+    pub(crate) stepped_frontier: HashSet<OwnedExecution>,
+    pub(crate) stepped_generation: usize,
+}
+
+impl Synthesizer {
+    pub fn run(&self) -> Result<Option<(OwnedExecution, RegisterIndex)>, SynthesisError> {
+        self.run_cancellable(&CancellationToken::new())
+    }
+
+    pub fn run_cancellable(
+        &self,
+        cancel: &CancellationToken,
+    ) -> SynthesisResult {
+        self.check_spec().inspect_err(|e| search_log!(error, "invalid spec: {}", e))?;
+        let cancel = match self.config.timeout {
+            Some(timeout) => cancel.child_with_deadline(Instant::now() + timeout),
+            None => cancel.clone(),
+        };
+
+        if self.config.rayon_pool.is_some() {
+            return self.config.install(|| self.run_strategy(&cancel));
+        }
+        match self.config.threads {
+            Some(threads) => {
+                let pool = ThreadPool::new(Configuration::new().num_threads(threads))
+                    .expect("could not start the search thread pool");
+                pool.install(|| self.run_strategy(&cancel))
+            }
+            None => self.run_strategy(&cancel),
+        }
+    }
+
+    pub fn encoder(&self) -> &dyn InstructionEncoder {
+        self.config.encoder.as_ref().map_or(&FixedWidthEncoder, |encoder| &**encoder)
+    }
+
+    pub fn encode_program(&self, instructions: &[Instruction]) -> Result<Vec<u8>, EncodeError> {
+        self.encoder().encode_program(instructions)
+    }
+
+    pub fn decode_program(&self, bytes: &[u8]) -> Result<Vec<Instruction>, DecodeError> {
+        self.encoder().decode_program(bytes)
+    }
+
+    pub(crate) fn check_spec(&self) -> Result<(), SynthesisError> {
+        let inputs = self.spec.inputs();
+        let arity = check_arity(&inputs)?;
+        if let Some(input) = self.spec.domains.iter().enumerate().position(|(input, &(lo, hi))| input >= arity || lo > hi) {
+            return Err(SynthesisError::InvalidDomain { input });
+        }
+        for (case, row) in inputs.iter().enumerate() {
+            if let Some(input) = (0..arity).position(|input| !in_domain(row[input], self.spec.domain(input))) {
+                return Err(SynthesisError::OutOfDomain { case, input });
+            }
+        }
+
+        // The initial transform decides how many registers there are to start
+        let mut register_count = check_arity(&self.inputs)?;
+        for (index, inst) in self.config.preamble.iter().enumerate() {
+            if !inst.is_valid_for(register_count) {
+                return Err(SynthesisError::InvalidPreamble { index });
+            }
+            register_count = inst.register_count_after(register_count);
+        }
+
+        let weights = self.config.test_case_weights.len();
+        if weights > 0 && weights != self.outputs.len() {
+            return Err(SynthesisError::WeightCount {
+                expected: self.outputs.len(),
+                found: weights,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn run_strategy(&self, cancel: &CancellationToken) -> SynthesisResult {
+        if let Strategy::GeneticAlgorithm { population, generations } = self.config.strategy {
+            let mut strategy = GASearchStrategy::new(population, generations);
+            if let Some(ref rng) = self.config.rng {
+                strategy = strategy.with_rng(rng.clone());
+            }
+            return Ok(strategy.run(&self.spec, cancel.clone()));
+        }
+        if let Strategy::RandomSampling { samples_per_gen } = self.config.strategy {
+            let mut strategy = RandomSamplingStrategy::new(samples_per_gen, self.config.max_depth);
+            if let Some(ref rng) = self.config.rng {
+                strategy = strategy.with_rng(rng.clone());
+            }
+            return Ok(strategy.run(&self.spec, cancel.clone()));
+        }
+
+        // Searches stop each other without touching the caller's token
+        let found = cancel.child();
+        if self.config.restart_seeds.is_empty() {
+            return self.search(None, &found);
+        }
+
+        self.config
+            .restart_seeds
+            .par_iter()
+            .map(|&seed| self.search(Some(seed), &found))
+            .find_any(|result| !matches!(*result, Ok(None)))
+            .unwrap_or(Ok(None))
+    }
+
+    // Breadth-first search; a solution cancels `done`, and giving up happens
+    // as soon as something else cancels it
+    fn search(&self, seed: Option<u64>, done: &CancellationToken) -> SynthesisResult {
+        let old_executions = ExecutionArena::new();
+        let restored = match seed {
+            None => self.state.lock().unwrap().clone(),
+            Some(_) => None,
+        };
+
+        let memo = MemoTable::new();
+        let mut pruner = self.config.histogram_pruning.map(|(threshold, generations)| {
+            HistogramPruner::new(threshold, generations)
+        });
+        let mut prev_generations = VecDeque::new();
+        let (mut last_generation, mut generation) = match restored {
+            Some(state) => (Some(state.rebuild(&old_executions, self)), state.generation() + 1),
+            None => {
+                let start_exec = self.start(&old_executions);
+                if let Some(reg) = self.output_register(&start_exec) {
+                    search_log!(info, "solved by the inputs alone, output in r{}", reg);
+                    self.print_solution(&start_exec, reg);
+                    self.notify_solution(&start_exec, reg);
+                    return Ok(Some((OwnedExecution::from(&start_exec), reg)));
+                }
+
+                let mut starts = IndexSet::new();
+                starts.insert(start_exec);
+                (Some(old_executions.alloc_generation(starts)), 1)
+            }
+        };
+        let register_cap = self.config.max_registers.filter(|_| !self.config.grow_registers);
+        let mut progress = SearchProgress::new(self.config.progress_bar);
+        if seed.is_none() {
+            self.generation_stats.lock().unwrap().clear();
+        }
+
+        loop {
+            let generation_started = Instant::now();
+            if done.is_cancelled() {
+                self.save_state(seed, last_generation.unwrap(), generation - 1);
+                self.notify_termination(TerminationReason::Cancelled);
+                return Ok(None);
+            }
+            if self.config.max_depth.is_some_and(|max_depth| generation > max_depth) {
+                self.save_state(seed, last_generation.unwrap(), generation - 1);
+                self.notify_termination(TerminationReason::DepthLimit);
+                return Ok(self.refine(last_generation.unwrap(), done));
+            }
+            if let Some(limit) = self.config.generation_limit.filter(|&limit| generation > limit) {
+                let frontier = last_generation.unwrap();
+                self.save_state(seed, frontier, generation - 1);
+                self.notify_termination(TerminationReason::GenerationLimit);
+                return Err(SynthesisError::GenerationLimitExceeded {
+                    generations_run: limit,
+                    frontier_size: frontier.len(),
+                });
+            }
+            search_log!(debug, "starting generation {}", generation);
+
+            let parents = last_generation.take().unwrap();
+            if self.config.dedup_lookback > 0 {
+                if prev_generations.len() == self.config.dedup_lookback {
+                    prev_generations.pop_front();
+                }
+                prev_generations.push_back(parents);
+            }
+            let old_programs = par_frontier(parents);
+
+            let new_programs = old_programs.flat_map(|parent| self.expand(parent, seed)).filter(|program| {
+                match (pruner.as_ref(), program.instruction.as_ref()) {
+                    (Some(pruner), Some(inst)) => pruner.allows(inst),
+                    _ => true,
+                }
+            });
+
+            // A no-op child repeats its parent, which was already searched, so
+            // it is dropped before paying for execute
+            let executed = AtomicUsize::new(0);
+            let new_executions = new_programs
+                .filter(|program| !program.is_noop_with(self.config.overflow))
+                .inspect(|_| {
+                    executed.fetch_add(1, Ordering::Relaxed);
+                })
+                .filter_map(|program| self.execute_child(program, &memo))
+                .filter(|exe| self.within_domains(exe));
+
+            let mut filtered_executions = self.collect_generation(
+                new_executions.filter(|newexe| prev_generations.iter().all(|prevgen| !prevgen.contains(newexe))),
+            );
+            if generation == 1 {
+                let start = parents.iter().next().unwrap();
+                filtered_executions.extend(self.seeds(&old_executions, start));
+            }
+            progress.generation(generation, filtered_executions.len());
+            let executed = executed.into_inner();
+            for monitor in &self.config.monitors {
+                monitor.on_executions(generation, executed);
+                monitor.on_generation(generation, &filtered_executions);
+            }
+            if seed.is_none() {
+                let best_score = par_frontier(&filtered_executions)
+                    .map(|exe| score(&exe.output, &self.outputs))
+                    .reduce(|| f64::NEG_INFINITY, f64::max);
+                self.generation_stats.lock().unwrap().push(GenerationStats {
+                    generation,
+                    elapsed: generation_started.elapsed(),
+                    frontier: filtered_executions.len(),
+                    bytes: par_frontier(&filtered_executions).map(execution_bytes).sum(),
+                    best_score,
+                });
+            }
+
+            // Nothing left to extend, e.g. every candidate left the domains
+            if filtered_executions.is_empty() {
+                search_log!(warn, "frontier is empty after generation {}", generation);
+                self.notify_termination(TerminationReason::FrontierEmpty);
+                return Ok(None);
+            }
+
+            if cfg!(feature = "dedup-check") && filtered_executions.len() <= DEDUP_CHECK_LIMIT {
+                if let Err(e) = verify_dedup_soundness(&filtered_executions) {
+                    panic!("generation {}: {}", generation, e);
+                }
+            }
+
+            if let Some((exe, reg)) = self.first_solution(&filtered_executions) {
+                done.cancel();
+                if self.config.resumable {
+                    self.save_state(seed, parents, generation - 1);
+                }
+                search_log!(info, "solved at generation {}, output in r{}", generation, reg);
+                self.print_solution(exe, reg);
+                self.notify_solution(exe, reg);
+                return Ok(Some((OwnedExecution::from(exe), reg)));
+            }
+
+            if let Some(cap) = register_cap {
+                if filtered_executions.iter().all(|exe| exe.output[0].len() >= cap) {
+                    self.notify_termination(TerminationReason::RegisterCapReached);
+                    return Err(SynthesisError::RegisterCapReached { generation });
+                }
+            }
+            if let Some(limit) = self.config.frontier_size_limit {
+                if filtered_executions.len() > limit {
+                    self.notify_termination(TerminationReason::FrontierLimit);
+                    return Err(SynthesisError::FrontierLimitExceeded {
+                        size: filtered_executions.len(),
+                    });
+                }
+            }
+            if let Some(ref mut pruner) = pruner {
+                pruner.observe(&filtered_executions);
+            }
+
+            last_generation = Some(old_executions.alloc_generation(filtered_executions));
+            generation += 1;
+        }
+    }
+
+    // The seeded programs that run and change something, each on its own
+    // chain of single-execution generations in `arena` under `start`
+    pub(crate) fn seeds<'a>(&self, arena: &'a ExecutionArena<'a>, start: &'a Execution<'a>) -> Vec<Execution<'a>> {
+        self.config
+            .seeded_programs
+            .iter()
+            .filter_map(|seed| {
+                let (last, prefix) = seed.split_last()?;
+                let mut register_count = start.output[0].len();
+                let mut parent = start;
+                for inst in prefix {
+                    if !inst.is_valid_for(register_count) {
+                        return None;
+                    }
+                    register_count = inst.register_count_after(register_count);
+                    let program = Program {
+                        parent: Some(parent),
+                        instruction: Some(inst.clone()),
+                        depth: parent.program.depth + 1,
+                    };
+                    let step = execute_with(program, self.config.overflow)?;
+                    parent = arena.alloc_generation(iter::once(step).collect()).iter().next().unwrap();
+                }
+                if !last.is_valid_for(register_count) {
+                    return None;
+                }
+                let program = Program {
+                    parent: Some(parent),
+                    instruction: Some(last.clone()),
+                    depth: parent.program.depth + 1,
+                };
+                let exe = execute_with(program, self.config.overflow)?;
+                Some(exe).filter(|exe| exe.output != start.output && self.within_domains(exe))
+            })
+            .collect()
+    }
+
+    // Only the unseeded search is resumable, since restarts reshuffle
+    fn save_state(&self, seed: Option<u64>, frontier: &IndexSet<Execution>, generation: usize) {
+        if seed.is_none() {
+            *self.state.lock().unwrap() = Some(SynthesizerState::from_frontier(frontier, generation));
+        }
+    }
+
+    pub(crate) fn output_register(&self, exe: &Execution) -> Option<RegisterIndex> {
+        self.hinted_register(exe)
+            .or_else(|| self.config.verification.verify_with(&exe.output, |reg| self.register_passes(exe, reg)))
+    }
+
+    // Whether `reg` holds the result, by weight if there are weights
+    fn register_passes(&self, exe: &Execution, reg: RegisterIndex) -> bool {
+        let weights = &self.config.test_case_weights;
+        if let Some(ref postprocess) = self.config.output_postprocessing {
+            let passes = |case: usize| (postprocess.0)(exe.output[case][reg.as_index()]) == self.outputs[case];
+            if weights.is_empty() {
+                return (0..self.outputs.len()).all(passes);
+            }
+            let passing = (0..self.outputs.len()).filter(|&case| passes(case)).map(|case| weights[case]).sum::<f64>();
+            return passing / weights.iter().sum::<f64>() >= self.config.weight_threshold;
+        }
+        if !weights.is_empty() {
+            passing_weight(&exe.output, reg, &self.outputs, weights) >= self.config.weight_threshold
+        } else if self.config.parallel_verify {
+            register_matches_par(&exe.output, reg, &self.outputs)
+        } else {
+            register_matches(&exe.output, reg, &self.outputs)
+        }
+    }
+
+    // The output register hint, if the policy allows any register and `exe`
+    // has the result there
+    fn hinted_register(&self, exe: &Execution) -> Option<RegisterIndex> {
+        let hint = self.config.output_register_hint?;
+        if self.config.verification != VerificationPolicy::AnyRegister || hint.as_index() >= exe.output[0].len() {
+            return None;
+        }
+        Some(hint).filter(|&hint| self.register_passes(exe, hint))
+    }
+
+    // The first solution in frontier order, taking one with the result in
+    // the hinted register over any other
+    fn first_solution<'a, 'b>(&self, frontier: &'b IndexSet<Execution<'a>>) -> Option<(&'b Execution<'a>, RegisterIndex)> {
+        let solution = |register: &(dyn Fn(&Execution) -> Option<RegisterIndex> + Sync)| {
+            par_frontier(frontier)
+                .map(|exe| (exe, register(exe)))
+                .find_first(|&(_, reg)| reg.is_some())
+                .map(|(exe, reg)| (exe, reg.unwrap()))
+        };
+        if self.config.output_register_hint.is_some() {
+            if let Some(found) = solution(&|exe| self.hinted_register(exe)) {
+                return Some(found);
+            }
+        }
+        solution(&|exe| self.output_register(exe))
+    }
+
+    fn notify_solution(&self, exe: &Execution, reg: RegisterIndex) {
+        for monitor in &self.config.monitors {
+            monitor.on_solution(exe, reg);
+        }
+        self.notify_termination(TerminationReason::Solved);
+    }
+
+    fn notify_termination(&self, reason: TerminationReason) {
+        for monitor in &self.config.monitors {
+            monitor.on_termination(reason);
+        }
+    }
+
+    fn print_solution(&self, exe: &Execution, reg: RegisterIndex) {
+        if let Some(ref printer) = self.config.printer {
+            // A closed output is no reason to lose the solution itself
+            let _ = match self.config.output {
+                Some(ref output) => {
+                    let mut writer = output.0.lock().unwrap();
+                    printer.print(exe, reg, &mut *writer).and_then(|()| writer.flush())
+                }
+                None => printer.print(exe, reg, &mut io::stdout().lock()),
+            };
+        }
+    }
+
+    // The inputs with the preamble applied. Each preamble step gets its own
+    // generation in `arena` so the result still linearizes to the whole
+    // program.
+    pub(crate) fn start<'a>(&self, arena: &'a ExecutionArena<'a>) -> Execution<'a> {
+        let mut exe = self.root();
+
+        for inst in &self.config.preamble {
+            let mut step = IndexSet::new();
+            step.insert(exe);
+            let parent = arena.alloc_generation(step).iter().next().unwrap();
+            exe = execute(Program {
+                parent: Some(parent),
+                instruction: Some(inst.clone()),
+                depth: parent.program.depth + 1,
+            });
+        }
+
+        exe
+    }
+
+    // The execution before any instruction, carrying the registers'
+    // starting intervals when the spec has domains
+    pub(crate) fn root<'a>(&self) -> Execution<'a> {
+        let mut exe = Execution::root(self.inputs.clone());
+        exe.intervals = self.root_intervals.clone();
+        exe
+    }
+
+    // Programs with a register that is out of domain for every in-domain
+    // input can only reach the spec through an out-of-domain intermediate,
+    // so they are pruned, as are programs with values outside
+    // with_value_domain
+    pub(crate) fn within_domains(&self, exe: &Execution) -> bool {
+        if let Some(domain) = self.config.value_domain {
+            if !exe.output.iter().flatten().all(|&value| in_domain(value, domain)) {
+                return false;
+            }
+        }
+        self.domain_bounds.is_none_or(|bounds| !leaves_domain(&exe.intervals, bounds))
+    }
+
+    // Every distinct solution exactly `depth` instructions long in the
+    // search space run explores: the builder's filters, overflow policy and
+    // domains apply, dedup lookback and pruning do not
+    pub fn find_all_at_depth(&self, depth: usize) -> Vec<(OwnedExecution, RegisterIndex)> {
+        self.config.install(|| {
+            let arena = ExecutionArena::new();
+            let memo = MemoTable::new();
+            let mut starts = IndexSet::new();
+            starts.insert(self.start(&arena));
+            let mut frontier = arena.alloc_generation(starts);
+
+            for _ in 0..depth {
+                frontier = arena.alloc_generation(self.next_generation(frontier, &memo));
+            }
+
+            par_frontier(frontier)
+                .filter_map(|exe| self.output_register(exe).map(|reg| (OwnedExecution::from(exe), reg)))
+                .collect()
+        })
+    }
+
+    // Whether some program shorter than `depth` instructions solves the
+    // spec, searching one generation at a time so it can stop early
+    pub(crate) fn solvable_below(&self, depth: usize) -> bool {
+        self.config.install(|| {
+            let arena = ExecutionArena::new();
+            let mut starts = IndexSet::new();
+            starts.insert(self.start(&arena));
+            let mut frontier = arena.alloc_generation(starts);
+
+            for shorter in 0..depth {
+                if par_frontier(frontier).any(|exe| self.output_register(exe).is_some()) {
+                    return true;
+                }
+                if shorter + 1 < depth {
+                    let next = self.collect_generation(par_frontier(frontier).flat_map(add_one_instruction).map(execute));
+                    frontier = arena.alloc_generation(next);
+                }
+            }
+            false
+        })
+    }
+
+    // Whether some program of at most `depth` instructions solves the spec,
+    // generating children as the search does, so the builder's filters and
+    // register cap apply. A frontier at the cap is no reason to stop here.
+    pub(crate) fn solvable_within(&self, depth: usize) -> bool {
+        self.config.install(|| {
+            let arena = ExecutionArena::new();
+            let memo = MemoTable::new();
+            let mut starts = IndexSet::new();
+            starts.insert(self.start(&arena));
+            let mut frontier = arena.alloc_generation(starts);
+
+            for length in 0..=depth {
+                if par_frontier(frontier).any(|exe| self.output_register(exe).is_some()) {
+                    return true;
+                }
+                if length < depth {
+                    frontier = arena.alloc_generation(self.next_generation(frontier, &memo));
+                }
+            }
+            false
+        })
+    }
+
+    // The distinct children of `frontier` as the search generates and
+    // filters them, without dedup lookback, seeds or monitors
+    pub(crate) fn next_generation<'a>(
+        &self,
+        frontier: &'a IndexSet<Execution<'a>>,
+        memo: &MemoTable,
+    ) -> IndexSet<Execution<'a>> {
+        self.collect_generation(self.children(frontier, memo))
+    }
+
+    // Every child of `frontier` the search keeps before deduplication, in
+    // frontier order: expanded with the builder's filters, run under its
+    // overflow policy and checked against the domains
+    pub(crate) fn children<'a, 'b>(
+        &'b self,
+        frontier: &'a IndexSet<Execution<'a>>,
+        memo: &'b MemoTable,
+    ) -> impl ParallelIterator<Item = Execution<'a>> + 'b
+    where
+        'a: 'b,
+    {
+        par_frontier(frontier)
+            .flat_map(move |parent| self.expand(parent, None))
+            .filter(move |program| !program.is_noop_with(self.config.overflow))
+            .filter_map(move |program| self.execute_child(program, memo))
+            .filter(move |exe| self.within_domains(exe))
+    }
+
+    // `program` run under the builder's overflow policy, through `memo` with
+    // with_execute_memo; None if the policy rejects it
+    pub(crate) fn execute_child<'a>(&self, program: Program<'a>, memo: &MemoTable) -> Option<Execution<'a>> {
+        if self.config.memoize_execute {
+            memo.execute(program, self.config.overflow)
+        } else {
+            execute_with(program, self.config.overflow)
+        }
+    }
+
+    // Children of `parent` the search considers: every one-instruction
+    // extension, minus forbidden patterns, mirrored commutative instructions
+    // when symmetry breaking is on, and new registers past the cap
+    pub(crate) fn expand<'a>(&self, parent: &'a Execution<'a>, seed: Option<u64>) -> Vec<Program<'a>> {
+        let mut programs = match seed {
+            Some(seed) => add_one_instruction_shuffled(parent, seed),
+            None => add_one_instruction(parent),
+        };
+        let register_count = parent.output[0].len();
+        programs.extend(
+            self.config
+                .fused_atoms
+                .iter()
+                .map(|atom| Instruction::Fused(atom.clone()))
+                .filter(|inst| inst.is_valid_for(register_count))
+                .filter(|inst| inst.register_count_after(register_count) <= MAX_REGISTERS)
+                .map(|inst| Program {
+                    parent: Some(parent),
+                    instruction: Some(inst),
+                    depth: parent.program.depth + 1,
+                }),
+        );
+        let forbidden = &self.config.forbidden_patterns;
+        if !forbidden.is_empty() {
+            programs.retain(|program| !forbidden.iter().any(|pattern| check_pattern(program, pattern)));
+        }
+        if let Some(ref enabled) = self.config.enabled_instructions {
+            programs.retain(|program| {
+                program.instruction.as_ref().is_none_or(|inst| enabled.contains(&inst.variant()))
+            });
+        }
+        if self.config.symmetry_breaking {
+            programs.retain(|program| program.instruction.as_ref().is_none_or(is_canonical_ordering));
+        }
+        if self.config.skip_mov_round_trips {
+            programs.retain(|program| !is_mov_round_trip(program));
+        }
+        let register_cap = self.config.max_registers.filter(|_| !self.config.grow_registers);
+        if register_cap.is_some_and(|cap| parent.output[0].len() >= cap) {
+            programs.retain(|program| !creates_register(program));
+        }
+        if !self.config.instruction_limits.is_empty() {
+            let mut counts = HashMap::new();
+            for inst in parent.instructions() {
+                *counts.entry(inst.variant()).or_insert(0) += 1;
+            }
+            let exhausted = self
+                .config
+                .instruction_limits
+                .iter()
+                .filter(|&(variant, &limit)| counts.get(variant).map_or(0, |&count| count) >= limit)
+                .map(|(&variant, _)| variant)
+                .collect::<Vec<_>>();
+            if !exhausted.is_empty() {
+                programs.retain(|program| {
+                    program.instruction.as_ref().is_none_or(|inst| !exhausted.contains(&inst.variant()))
+                });
+            }
+        }
+        programs
+    }
+
+    pub(crate) fn collect_generation<'a>(
+        &self,
+        executions: impl ParallelIterator<Item = Execution<'a>>,
+    ) -> IndexSet<Execution<'a>> {
+        if self.config.parallel_dedup {
+            collect_frontier(executions)
+        } else {
+            collect_ordered(executions)
+        }
+    }
+
+    // Distinct executions left after `generations` rounds of the search's
+    // expansion, filters and overflow policy, without checking any of them
+    // against the spec
+    pub fn frontier_size(&self, generations: usize) -> usize {
+        self.config.install(|| {
+            let arena = ExecutionArena::new();
+            let memo = MemoTable::new();
+            let mut starts = IndexSet::new();
+            starts.insert(self.start(&arena));
+            let mut frontier = arena.alloc_generation(starts);
+
+            for _ in 0..generations {
+                frontier = arena.alloc_generation(self.next_generation(frontier, &memo));
+            }
+
+            frontier.len()
+        })
+    }
+
+    fn refine(&self, frontier: &IndexSet<Execution>, done: &CancellationToken) -> Option<(OwnedExecution, RegisterIndex)> {
+        let refiner = self.config.refinement.as_ref()?;
+
+        // Only the best candidates are worth taking out of the arena
+        let mut ranked = frontier.iter().collect::<Vec<_>>();
+        ranked.sort_by(|a, b| score(&b.output, &self.outputs).total_cmp(&score(&a.output, &self.outputs)));
+        let candidates = ranked.into_iter().take(refiner.pool()).map(OwnedExecution::from).collect();
+
+        let result = refiner.refine(candidates, &self.outputs, &mut SearchRng::from_config(&self.config.rng), done);
+        if result.is_some() {
+            done.cancel();
+        }
+        result
+    }
+}
+
+// The shared row length of `rows`
+fn check_arity(rows: &[Vec<Value>]) -> Result<usize, SynthesisError> {
+    let arity = match rows.first() {
+        Some(row) => row.len(),
+        None => return Err(SynthesisError::EmptySpec),
+    };
+    if arity == 0 {
+        return Err(SynthesisError::NoInputRegisters);
+    }
+    if let Some(case) = rows.iter().position(|row| row.len() != arity) {
+        return Err(SynthesisError::InconsistentArity {
+            case,
+            expected: arity,
+            found: rows[case].len(),
+        });
+    }
+    Ok(arity)
+}
+
+// Only a Mov can write one past the parent's last register
+fn creates_register(program: &Program) -> bool {
+    match (program.instruction.as_ref(), program.parent) {
+        (Some(inst), Some(parent)) => inst.affects_register(Register::new(parent.output[0].len())),
+        _ => false,
+    }
+}
+
+impl SearchStrategy for SynthesizerBuilder {
+    fn run(
+        &self,
+        spec: &TestSuite,
+        cancel: CancellationToken,
+    ) -> Option<(OwnedExecution, RegisterIndex)> {
+        // The trait has no room for errors; an invalid spec has no solution
+        self.build(spec)
+            .run_cancellable(&cancel)
+            .unwrap_or(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testsuite::{TestCase, TestSuite};
+    use {Instruction, InstructionVariant, Register, SynthesizerBuilder};
+
+    fn negate() -> TestSuite {
+        TestSuite::new(vec![TestCase::new(vec![3], -3), TestCase::new(vec![-2], 2), TestCase::new(vec![5], -5)])
+    }
+
+    #[test]
+    fn find_all_at_depth_follows_enabled_instructions() {
+        let solutions = SynthesizerBuilder::new().build(&negate()).find_all_at_depth(1);
+        assert!(solutions.iter().any(|(exe, _)| exe.instructions == vec![Instruction::Neg(Register::new(0))]));
+
+        let no_neg = SynthesizerBuilder::new()
+            .with_enabled_instructions(vec![InstructionVariant::Mov, InstructionVariant::Add]);
+        assert!(no_neg.build(&negate()).find_all_at_depth(1).is_empty());
+    }
+
+    // y - x needs -x, which is below the domains and every output
+    #[test]
+    fn domains_prune_out_of_domain_intermediates() {
+        let cases = vec![TestCase::new(vec![1, 3], 2), TestCase::new(vec![2, 3], 1), TestCase::new(vec![1, 2], 1)];
+        let unbounded = TestSuite::new(cases);
+        let bounded = unbounded.clone().with_domain(0, 1, 3).with_domain(1, 1, 3);
+
+        let r = Register::new;
+        let subtract = vec![Instruction::Neg(r(0)), Instruction::Add(r(0), r(1))];
+        let solutions = SynthesizerBuilder::new().build(&unbounded).find_all_at_depth(2);
+        assert!(solutions.iter().any(|(exe, _)| exe.instructions == subtract));
+        assert!(SynthesizerBuilder::new().build(&bounded).find_all_at_depth(2).is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::iter::FromIterator;
+use std::slice;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+use {OwnedExecution, RegisterIndex, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct TestCase {
+    pub inputs: Vec<Value>,
+    pub output: Value,
+}
+
+impl TestCase {
+    pub fn new(inputs: Vec<Value>, output: Value) -> TestCase {
+        TestCase { inputs, output }
+    }
+}
+
+// Inclusive bounds every value of one input register lies in
+pub type Domain = (Value, Value);
+
+pub const UNBOUNDED: Domain = (Value::MIN, Value::MAX);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct TestSuite {
+    pub cases: Vec<TestCase>,
+    // One entry per input register, or empty if nothing is known. Inputs
+    // without an annotation are UNBOUNDED.
+    pub domains: Vec<Domain>,
+}
+
+// Backs named_testcases!
+#[doc(hidden)]
+pub fn named_suite(cases: Vec<(Vec<&'static str>, TestCase)>) -> (TestSuite, Vec<&'static str>) {
+    let names = cases.first().map(|(names, _)| names.clone()).unwrap_or_default();
+    if let Some(case) = cases.iter().position(|(case_names, _)| *case_names != names) {
+        panic!("test case {} names its inputs {:?}, expected {:?}", case, cases[case].0, names);
+    }
+    (TestSuite::new(cases.into_iter().map(|(_, case)| case).collect()), names)
+}
+
+impl TestSuite {
+    pub fn new(cases: Vec<TestCase>) -> TestSuite {
+        TestSuite {
+            cases,
+            domains: vec![],
+        }
+    }
+
+    // Declare that input `input` only ever takes values in `lo..=hi`
+    pub fn with_domain(mut self, input: usize, lo: Value, hi: Value) -> Self {
+        let arity = self.cases.first().map_or(0, |case| case.inputs.len());
+        if self.domains.len() <= input {
+            self.domains.resize(arity.max(input + 1), UNBOUNDED);
+        }
+        self.domains[input] = (lo, hi);
+        self
+    }
+
+    // The `where inputN in [lo, hi]` clause of testcases!
+    #[doc(hidden)]
+    pub fn with_named_domain(self, name: &str, lo: Value, hi: Value) -> Self {
+        let input = name
+            .strip_prefix("input")
+            .and_then(|index| index.parse().ok())
+            .unwrap_or_else(|| panic!("domains are declared as `inputN in [lo, hi]`, not `{}`", name));
+        self.with_domain(input, lo, hi)
+    }
+
+    pub fn domain(&self, input: usize) -> Domain {
+        self.domains.get(input).cloned().unwrap_or(UNBOUNDED)
+    }
+
+    pub fn cases(&self) -> &[TestCase] {
+        &self.cases
+    }
+
+    pub fn len(&self) -> usize {
+        self.cases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cases.is_empty()
+    }
+
+    pub fn push(&mut self, case: TestCase) {
+        self.cases.push(case);
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, TestCase> {
+        self.cases.iter()
+    }
+
+    // One row of input registers per test case, the layout executions start from
+    pub fn inputs(&self) -> Vec<Vec<Value>> {
+        self.cases.iter().map(|case| case.inputs.clone()).collect()
+    }
+
+    pub fn outputs(&self) -> Vec<Value> {
+        self.cases.iter().map(|case| case.output).collect()
+    }
+
+    // A random `ratio` of the cases, rounded, to synthesize from, and the
+    // rest to check the result on. Both keep the cases in their original
+    // order, and the domains.
+    pub fn split_train_test(&self, ratio: f64, rng: &mut impl Rng) -> (TestSuite, TestSuite) {
+        assert!((0.0..=1.0).contains(&ratio), "the training ratio must be between 0 and 1");
+        let mut order = (0..self.cases.len()).collect::<Vec<_>>();
+        order.shuffle(rng);
+        let mut training = vec![false; self.cases.len()];
+        for &case in &order[..(ratio * self.cases.len() as f64).round() as usize] {
+            training[case] = true;
+        }
+
+        let split = |keep: bool| TestSuite {
+            cases: self
+                .cases
+                .iter()
+                .zip(&training)
+                .filter(|&(_, &train)| train == keep)
+                .map(|(case, _)| case.clone())
+                .collect(),
+            domains: self.domains.clone(),
+        };
+        (split(true), split(false))
+    }
+
+    // Whether the program behind `exe` leaves every case's output in
+    // `output_register`, as for checking a solution on held-out cases
+    pub fn is_solved_by(&self, exe: &OwnedExecution, output_register: RegisterIndex) -> bool {
+        self.cases.iter().all(|case| exe.evaluate(output_register, &case.inputs) == case.output)
+    }
+}
+
+impl FromIterator<TestCase> for TestSuite {
+    fn from_iter<I: IntoIterator<Item = TestCase>>(iter: I) -> TestSuite {
+        TestSuite::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a TestSuite {
+    type Item = &'a TestCase;
+    type IntoIter = slice::Iter<'a, TestCase>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cases.iter()
+    }
+}
@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt;
+
+use Execution;
+
+// Each variant names the depth of the node that broke the invariant
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgramValidationError {
+    // A node with a parent but no instruction
+    MissingInstruction { depth: usize },
+    // The root carries an instruction or a nonzero depth
+    MalformedRoot { depth: usize },
+    // The instruction uses a register its parent does not have
+    InvalidRegister { depth: usize },
+    // Test case `case` has a different register count than the first
+    RaggedOutput { depth: usize, case: usize },
+    DepthMismatch { depth: usize, parent_depth: usize },
+}
+
+impl fmt::Display for ProgramValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProgramValidationError::MissingInstruction { depth } => {
+                write!(f, "node at depth {} has a parent but no instruction", depth)
+            }
+            ProgramValidationError::MalformedRoot { depth } => {
+                write!(f, "root node has depth {} or an instruction", depth)
+            }
+            ProgramValidationError::InvalidRegister { depth } => {
+                write!(f, "instruction at depth {} uses a register its parent does not have", depth)
+            }
+            ProgramValidationError::RaggedOutput { depth, case } => {
+                write!(f, "output at depth {} has a different register count in test case {}", depth, case)
+            }
+            ProgramValidationError::DepthMismatch { depth, parent_depth } => {
+                write!(f, "node at depth {} has a parent at depth {}", depth, parent_depth)
+            }
+        }
+    }
+}
+
+impl Error for ProgramValidationError {}
+
+// Walk the parent chain checking the invariants add_one_instruction and
+// execute are meant to uphold
+pub fn validate_program(exe: &Execution) -> Result<(), ProgramValidationError> {
+    let mut current = Some(exe);
+
+    while let Some(node) = current {
+        let depth = node.program.depth;
+
+        let width = node.output[0].len();
+        if let Some(case) = node.output.iter().position(|row| row.len() != width) {
+            return Err(ProgramValidationError::RaggedOutput { depth, case });
+        }
+
+        match (node.program.parent, node.program.instruction.as_ref()) {
+            (None, None) if depth == 0 => {}
+            (None, _) => return Err(ProgramValidationError::MalformedRoot { depth }),
+            (Some(_), None) => return Err(ProgramValidationError::MissingInstruction { depth }),
+            (Some(parent), Some(inst)) => {
+                if parent.program.depth + 1 != depth {
+                    return Err(ProgramValidationError::DepthMismatch {
+                        depth,
+                        parent_depth: parent.program.depth,
+                    });
+                }
+                if !inst.is_valid_for(parent.output[0].len()) {
+                    return Err(ProgramValidationError::InvalidRegister { depth });
+                }
+            }
+        }
+
+        current = node.program.parent;
+    }
+
+    Ok(())
+}
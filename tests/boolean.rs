@@ -0,0 +1,64 @@
+extern crate vasm_sim;
+
+use std::collections::BTreeSet;
+
+use vasm_sim::BoolInstruction::{And, Nand, Nor, Not, Or, Xor};
+use vasm_sim::{bool_canonicalize, BoolInstruction, BooleanExecutor, BooleanSynthesizer, Register, SynthesisError};
+
+// Two-input truth table rows, in the usual order
+fn rows() -> Vec<Vec<bool>> {
+    vec![vec![false, false], vec![false, true], vec![true, false], vec![true, true]]
+}
+
+// Every test case needs exactly one expected output: extras would fall
+// outside the truth table, and missing ones would silently become false
+#[test]
+fn output_count_must_match_cases() {
+    for &count in &[3, 5, 65] {
+        let outputs = vec![true; count];
+        match BooleanSynthesizer::new(&rows(), &outputs) {
+            Err(SynthesisError::OutputCount { expected, found }) => assert_eq!((expected, found), (4, count)),
+            other => panic!("{} outputs: {:?}", count, other.map(|_| ())),
+        }
+    }
+
+    let xor = BooleanSynthesizer::new(&rows(), &[false, true, true, false]).unwrap();
+    assert_eq!(xor.run().unwrap().0.len(), 1);
+}
+
+// The columns of `program` over all 2^n_inputs assignments, as a set
+fn functions(program: &[BoolInstruction], n_inputs: usize) -> BTreeSet<u64> {
+    let rows = 1 << n_inputs;
+    let inputs = (0..n_inputs)
+        .map(|input| (0..rows).filter(|row| row >> input & 1 == 1).fold(0, |column, row| column | 1 << row))
+        .collect::<Vec<u64>>();
+    BooleanExecutor::new(rows).execute(&inputs, program).into_iter().collect()
+}
+
+// Dropping recomputed functions must keep every function the program
+// computes, including in programs that never read their highest input
+#[test]
+fn canonicalize_keeps_truth_table() {
+    let (r0, r1, r2, r3, r4, r5) = (
+        Register::new(0),
+        Register::new(1),
+        Register::new(2),
+        Register::new(3),
+        Register::new(4),
+        Register::new(5),
+    );
+    let programs = vec![
+        (3, vec![Not(r0), Not(r1), And(r3, r1), And(r2, r0)]),
+        (3, vec![And(r0, r1), Not(r3), Not(r4)]),
+        (2, vec![Not(r0), Not(r2), Or(r3, r1), Or(r0, r1), Xor(r4, r5)]),
+        (4, vec![Nand(r0, r0), Nor(r1, r1)]),
+    ];
+    for (n_inputs, program) in programs {
+        let canonical = bool_canonicalize(&program, n_inputs);
+        assert!(canonical.len() <= program.len());
+        assert_eq!(functions(&canonical, n_inputs), functions(&program, n_inputs), "{:?}", program);
+    }
+
+    // not of a not is its input again
+    assert_eq!(bool_canonicalize(&[Not(r0), Not(r2)], 2), vec![Not(r0)]);
+}
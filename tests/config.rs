@@ -0,0 +1,29 @@
+#![cfg(feature = "config")]
+
+extern crate vasm_sim;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use vasm_sim::SynthesizerConfig;
+
+// A user's file with a zero beam width is an error to report, not a
+// builder assertion to trip over
+#[test]
+fn zero_beam_width_is_rejected() {
+    let path = env::temp_dir().join(format!("vasm-sim-config-{}.toml", process::id()));
+    fs::write(&path, "beam_width = 0\n").unwrap();
+    let error = SynthesizerConfig::from_toml(&path).unwrap_err();
+    fs::write(&path, "beam_width = 4\n").unwrap();
+    let config = SynthesizerConfig::from_toml(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(error.to_string().contains("beam_width must be at least 1"), "{}", error);
+    assert_eq!(config.beam_width, Some(4));
+    SynthesizerConfig {
+        beam_width: Some(0),
+        ..SynthesizerConfig::default()
+    }
+    .into_builder();
+}
@@ -0,0 +1,88 @@
+extern crate rayon;
+#[macro_use]
+extern crate vasm_sim;
+
+use rayon::{Configuration, ThreadPool};
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use vasm_sim::{Instruction, OwnedExecution, Register, SynthesizerBuilder, TestSuite};
+
+// 2a + b, which several programs of the same length compute
+fn spec() -> TestSuite {
+    testcases![([1, 2], 4), ([4, -1], 7), ([0, 5], 5), ([3, 3], 9),]
+}
+
+// Frontiers keep insertion order and the first solution in that order
+// wins, so threads finishing in a different order change nothing
+#[test]
+fn same_seed_same_program() {
+    for &parallel_dedup in &[false, true] {
+        let run = || {
+            SynthesizerBuilder::new()
+                .with_seed(7)
+                .with_generation_limit(5)
+                .with_threads(4)
+                .with_parallel_dedup(parallel_dedup)
+                .build(&spec())
+                .run()
+                .unwrap()
+                .unwrap()
+        };
+        let (first, first_register) = run();
+        for _ in 0..5 {
+            let (exe, register) = run();
+            assert_eq!(exe.instructions, first.instructions);
+            assert_eq!(register, first_register);
+        }
+    }
+}
+
+// Sharing a pool between synthesizers changes where the work runs, not
+// what it finds
+#[test]
+fn shared_pool_same_program() {
+    let pool = Arc::new(ThreadPool::new(Configuration::new().num_threads(2)).unwrap());
+    let run = |builder: SynthesizerBuilder| builder.with_seed(7).with_generation_limit(5).build(&spec()).run().unwrap();
+    let alone = run(SynthesizerBuilder::new()).unwrap();
+    for _ in 0..3 {
+        let shared = run(SynthesizerBuilder::new().with_rayon_pool(pool.clone())).unwrap();
+        assert_eq!(shared.0.instructions, alone.0.instructions);
+        assert_eq!(shared.1, alone.1);
+    }
+}
+
+// Every HashSet hashes differently, so warm_start has to put the programs
+// in an order of its own for the first solution to stay the same
+#[test]
+fn warm_start_same_program() {
+    let (r0, r1, r2) = (Register::new(0), Register::new(1), Register::new(2));
+    let starts = [
+        Instruction::Add(r0, r0),
+        Instruction::Add(r1, r0),
+        Instruction::Mov(r0, r2),
+        Instruction::Add(r0, r1),
+        Instruction::Mov(r1, r2),
+    ];
+    let run = || {
+        let frontier = starts
+            .iter()
+            .map(|inst| OwnedExecution::replay(spec().inputs(), vec![inst.clone()]))
+            .collect::<HashSet<_>>();
+        SynthesizerBuilder::new()
+            .with_generation_limit(5)
+            .build(&spec())
+            .warm_start(frontier)
+            .unwrap()
+            .run()
+            .unwrap()
+            .unwrap()
+    };
+    let (first, first_register) = run();
+    for _ in 0..10 {
+        let (exe, register) = run();
+        assert_eq!(exe.instructions, first.instructions);
+        assert_eq!(register, first_register);
+    }
+}
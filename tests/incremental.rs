@@ -0,0 +1,59 @@
+#[macro_use]
+extern crate vasm_sim;
+
+use vasm_sim::{Instruction, Register, Synthesizer, SynthesizerBuilder};
+
+// x^4 takes two squarings, so one generation at a time the search is done
+// on the second call and stays done
+#[test]
+fn run_n_generations_stops_at_solution() {
+    let spec = testcases![([2], 16), ([-3], 81), ([1], 1),];
+    let mut synthesizer = SynthesizerBuilder::new().build(&spec);
+
+    let first = synthesizer.run_n_generations(1);
+    assert_eq!((first.generation, first.done), (1, false));
+    assert!(first.solutions.is_empty());
+    assert_eq!(synthesizer.current_generation(), 1);
+    assert_eq!(synthesizer.current_frontier().len(), first.frontier_size);
+
+    let second = synthesizer.run_n_generations(1);
+    assert_eq!((second.generation, second.done), (2, true));
+    let square = Instruction::Mul(Register::new(0), Register::new(0));
+    assert!(second
+        .solutions
+        .iter()
+        .any(|&(ref exe, reg)| exe.instructions == vec![square.clone(), square.clone()] && reg == Register::new(0)));
+
+    let again = synthesizer.run_n_generations(3);
+    assert_eq!((again.generation, again.solutions.len()), (2, second.solutions.len()));
+}
+
+// run searches on from the stepped frontier but leaves current_frontier as
+// the last run_n_generations left it
+#[test]
+fn current_frontier_is_stale_after_run() {
+    let spec = testcases![([2], 16), ([-3], 81), ([1], 1),];
+    let mut synthesizer = SynthesizerBuilder::new().build(&spec);
+    synthesizer.run_n_generations(1);
+    let stepped = synthesizer.current_frontier().clone();
+
+    assert!(synthesizer.run().unwrap().is_some());
+    assert_eq!(synthesizer.current_generation(), 1);
+    assert_eq!(*synthesizer.current_frontier(), stepped);
+}
+
+// A solution inside the three benchmark generations is certain; a spec
+// the search cannot finish that soon is only extrapolated
+#[test]
+fn benchmark_spec_confidence() {
+    let builder = SynthesizerBuilder::new();
+    let fourth_power = testcases![([2], 16), ([-3], 81), ([1], 1),];
+    let seen = Synthesizer::benchmark_spec(&fourth_power, &builder);
+    assert_eq!(seen.estimated_depth, Some(2));
+    assert_eq!(seen.confidence, 1.0);
+
+    let hard = testcases![([1, 2], 7), ([4, -1], 19), ([0, 5], 25), ([3, 3], 21),];
+    let guessed = Synthesizer::benchmark_spec(&hard, &builder);
+    assert!(guessed.estimated_depth.is_none_or(|depth| depth > 3));
+    assert!(guessed.confidence < 1.0);
+}
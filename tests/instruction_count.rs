@@ -0,0 +1,29 @@
+extern crate vasm_sim;
+
+use vasm_sim::{add_one_instruction, Execution, InstructionVariant, SynthesizerBuilder, MAX_REGISTERS};
+
+// The bound is exact, including at the register limit where there is no
+// new register to copy into
+#[test]
+fn bound_matches_generated_programs() {
+    for &register_count in &[1, 2, 5, MAX_REGISTERS] {
+        let root = Execution::root(vec![vec![0; register_count]]);
+        assert_eq!(
+            add_one_instruction(&root).len(),
+            SynthesizerBuilder::instruction_count_bound(register_count),
+            "{} registers",
+            register_count
+        );
+    }
+}
+
+#[test]
+fn enabled_instructions_follow_the_builder() {
+    assert_eq!(
+        SynthesizerBuilder::new().enabled_instructions(),
+        vec![InstructionVariant::Mov, InstructionVariant::Add, InstructionVariant::Mul, InstructionVariant::Neg]
+    );
+    let builder =
+        SynthesizerBuilder::new().with_enabled_instructions(vec![InstructionVariant::Neg, InstructionVariant::Add]);
+    assert_eq!(builder.enabled_instructions(), vec![InstructionVariant::Add, InstructionVariant::Neg]);
+}
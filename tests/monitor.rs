@@ -0,0 +1,61 @@
+extern crate serde_json;
+#[macro_use]
+extern crate vasm_sim;
+
+use serde_json::Value;
+
+use std::sync::Arc;
+
+use vasm_sim::{run_with_logging, MonitorEvent, RecordingMonitor, SynthesizerBuilder, TerminationReason};
+
+// One event per generation, then the solution, then why it stopped
+#[test]
+fn recording_monitor_sees_every_phase() {
+    let monitor = Arc::new(RecordingMonitor::new());
+    let spec = testcases![([2], 16), ([-3], 81), ([1], 1),];
+    let (exe, reg) = SynthesizerBuilder::new()
+        .with_generation_limit(5)
+        .with_monitor(monitor.clone())
+        .build(&spec)
+        .run()
+        .unwrap()
+        .unwrap();
+
+    let events = monitor.events();
+    let generations = events
+        .iter()
+        .filter(|event| matches!(**event, MonitorEvent::Generation { .. }))
+        .count();
+    assert_eq!(generations, exe.instructions.len());
+    assert_eq!(
+        events[events.len() - 2..].to_vec(),
+        vec![
+            MonitorEvent::Solution {
+                instructions: exe.instructions.clone(),
+                register: reg,
+            },
+            MonitorEvent::Termination(TerminationReason::Solved),
+        ]
+    );
+}
+
+// A JSON line per generation, then one saying what was found
+#[test]
+fn run_with_logging_writes_json_lines() {
+    let spec = testcases![([2], 16), ([-3], 81), ([1], 1),];
+    let mut log = Vec::new();
+    let (exe, _) = run_with_logging(&spec, SynthesizerBuilder::new().with_generation_limit(5), &mut log).unwrap().unwrap();
+
+    let lines = String::from_utf8(log).unwrap();
+    let lines = lines.lines().map(|line| serde_json::from_str::<Value>(line).unwrap()).collect::<Vec<_>>();
+    assert_eq!(lines.len(), exe.instructions.len() + 1);
+    for (gen, line) in lines[..exe.instructions.len()].iter().enumerate() {
+        assert_eq!(line["gen"], gen + 1);
+        assert!(line["frontier"].as_u64().unwrap() > 0);
+        assert!(line["executions_run"].as_u64().unwrap() >= line["frontier"].as_u64().unwrap());
+    }
+    let last = &lines[lines.len() - 1];
+    assert_eq!(last["found"], true);
+    assert_eq!(last["depth"], exe.instructions.len());
+    assert_eq!(last["program"], "mul r0 r0; mul r0 r0");
+}
@@ -0,0 +1,127 @@
+#[macro_use]
+extern crate vasm_sim;
+
+use vasm_sim::{find_minimal_register_count, verify_is_minimal, SynthesizerBuilder, TestSuite};
+
+// Small problems with known shortest programs
+fn regression_suite() -> Vec<(&'static str, TestSuite)> {
+    vec![
+        ("identity", testcases![([3], 3), ([-2], -2), ([0], 0),]),
+        ("double", testcases![([3], 6), ([-2], -4), ([5], 10),]),
+        ("square", testcases![([3], 9), ([-2], 4), ([5], 25),]),
+        ("negate", testcases![([3], -3), ([-2], 2), ([5], -5),]),
+        ("sum", testcases![([1, 2], 3), ([4, -1], 3), ([0, 5], 5), ([2, 2], 4),]),
+        ("difference", testcases![([1, 2], -1), ([4, -1], 5), ([0, 5], -5), ([2, 2], 0),]),
+        ("fourth_power", testcases![([2], 16), ([-3], 81), ([1], 1),]),
+        ("product_plus_first", testcases![([1, 2], 3), ([4, -1], 0), ([0, 5], 0), ([3, 3], 12),]),
+    ]
+}
+
+// The breadth-first search must stop at the shortest solution: nothing one
+// instruction shorter solves the spec, and exhaustive enumeration at the
+// same depth finds a solution too. A failure here means deduplication or
+// pruning dropped a solution, or the search stopped early.
+#[test]
+fn verify_timing_invariant() {
+    for (name, spec) in regression_suite() {
+        let synthesizer = SynthesizerBuilder::new().with_generation_limit(5).build(&spec);
+        let (exe, _) = synthesizer
+            .run()
+            .unwrap()
+            .unwrap_or_else(|| panic!("{}: no program found", name));
+        let depth = exe.instructions.len();
+
+        assert!(
+            verify_is_minimal(&spec, depth, &SynthesizerBuilder::new()),
+            "{}: a solution shorter than depth {} exists",
+            name,
+            depth
+        );
+        assert!(
+            !synthesizer.find_all_at_depth(depth).is_empty(),
+            "{}: search found depth {} but enumeration finds nothing there",
+            name,
+            depth
+        );
+    }
+}
+
+// 4x fits in its input register, while x*x + x has to copy x before
+// squaring it
+#[test]
+fn minimal_register_count() {
+    let builder = SynthesizerBuilder::new();
+    let quadruple = testcases![([3], 12), ([-2], -8), ([5], 20),];
+    assert_eq!(find_minimal_register_count(&quadruple, 2, &builder), Some(1));
+    let square_plus_self = testcases![([3], 12), ([-2], 2), ([5], 30),];
+    assert_eq!(find_minimal_register_count(&square_plus_self, 3, &builder), Some(2));
+}
+
+// x^8 takes three squarings, so two instructions are too few with any
+// number of registers
+#[test]
+fn minimal_register_count_unsolvable() {
+    let eighth_power = testcases![([2], 256), ([-1], 1), ([3], 6561),];
+    let builder = SynthesizerBuilder::new();
+    assert_eq!(find_minimal_register_count(&eighth_power, 2, &builder), None);
+    assert_eq!(find_minimal_register_count(&eighth_power, 3, &builder), Some(1));
+}
+
+// Every program over x alone computes a polynomial with no constant term,
+// so x + 1 needs a 1 in the register file, and then a single add
+#[test]
+fn register_file_init_saves_depth() {
+    let successor = testcases![([3], 4), ([-2], -1), ([5], 6),];
+    let (exe, reg) = SynthesizerBuilder::new()
+        .with_generation_limit(5)
+        .with_register_file_init(vec![0, 1, -1])
+        .build(&successor)
+        .run()
+        .unwrap()
+        .unwrap();
+    assert_eq!(exe.instructions.len(), 1);
+    assert_eq!(exe.evaluate(reg, &[10, 0, 1, -1]), 11);
+
+    let plain = SynthesizerBuilder::new().with_generation_limit(3).build(&successor).run();
+    assert!(plain.is_err());
+}
+
+// |a - b| is no polynomial, but with each pair sorted it is b - a
+#[test]
+fn input_preprocessing_sorts_pairs() {
+    let distance = testcases![([1, 4], 3), ([4, 1], 3), ([5, 2], 3), ([2, 7], 5), ([0, -3], 3),];
+    let (exe, reg) = SynthesizerBuilder::new()
+        .with_generation_limit(3)
+        .with_input_preprocessing(|inputs| inputs.sort())
+        .build(&distance)
+        .run()
+        .unwrap()
+        .unwrap();
+    assert_eq!(exe.instructions.len(), 2);
+    assert_eq!(exe.evaluate(reg, &[-6, 10]), 16);
+}
+
+// Squares kept to eight bits: the register holds x * x in full, which only
+// matches the targets after the same reduction
+#[test]
+fn output_postprocessing_compares_modulo() {
+    let square_mod_256 = testcases![([20], 144), ([17], 33), ([-30], 132), ([3], 9),];
+    let (exe, reg) = SynthesizerBuilder::new()
+        .with_generation_limit(3)
+        .with_output_postprocessing(|value| value.rem_euclid(256))
+        .build(&square_mod_256)
+        .run()
+        .unwrap()
+        .unwrap();
+    assert_eq!(exe.instructions.len(), 1);
+    assert_eq!(exe.evaluate(reg, &[20]), 400);
+}
+
+// Repeated constants, or a constant equal to an input that is always 0,
+// are not inputs the user aliased
+#[test]
+fn register_file_init_is_not_aliasing() {
+    let zero_and_double = testcases![([0, 3], 6), ([0, -2], -4), ([0, 5], 10),];
+    let synthesizer = SynthesizerBuilder::new().with_register_file_init(vec![0, 0]).build(&zero_and_double);
+    assert!(synthesizer.check_register_aliasing().is_empty());
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "serialization")]
+
+extern crate serde_json;
+extern crate vasm_sim;
+
+use vasm_sim::{execute, Execution, Instruction, OwnedExecution, Program, Register};
+
+fn inputs() -> Vec<Vec<isize>> {
+    vec![vec![3, -7], vec![0, 5], vec![-2, 4]]
+}
+
+fn every_variant() -> Vec<Instruction> {
+    let (r0, r1, r2) = (Register::new(0), Register::new(1), Register::new(2));
+    vec![
+        Instruction::Mov(r0, r2),
+        Instruction::Mov(r1, r0),
+        Instruction::Add(r0, r1),
+        Instruction::Mul(r1, r0),
+        Instruction::Neg(r1),
+    ]
+}
+
+#[test]
+fn every_instruction_variant_round_trips() {
+    let root = Execution::root(inputs());
+
+    for inst in every_variant() {
+        let exe = execute(Program {
+            parent: Some(&root),
+            instruction: Some(inst.clone()),
+            depth: 1,
+        });
+
+        let json = serde_json::to_string(&exe).unwrap();
+        let restored: OwnedExecution = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.instructions, vec![inst.clone()], "{}", json);
+        let replayed = OwnedExecution::replay(inputs(), restored.instructions);
+        assert_eq!(replayed.output, exe.output, "{:?}", inst);
+    }
+}
@@ -0,0 +1,36 @@
+extern crate rand;
+#[macro_use]
+extern crate vasm_sim;
+
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use vasm_sim::SynthesizerBuilder;
+
+// Squares of six inputs. Two cases often have a shorter explanation than
+// squaring, like the identity for 0 and 1 or doubling for 0 and 2, and a
+// program found from those fails on the held-out cases.
+#[test]
+fn underdetermined_training_set_does_not_generalize() {
+    let spec = testcases![([0], 0), ([1], 1), ([2], 4), ([3], 9), ([-1], 1), ([-2], 4),];
+    let mut failures = 0;
+    for seed in 0..20 {
+        let (training, validation) = spec.split_train_test(1.0 / 3.0, &mut SmallRng::seed_from_u64(seed));
+        assert_eq!((training.len(), validation.len()), (2, 4));
+
+        let (exe, reg) = SynthesizerBuilder::new()
+            .with_generation_limit(3)
+            .build(&training)
+            .run()
+            .unwrap()
+            .unwrap();
+        assert!(training.is_solved_by(&exe, reg));
+        if !validation.is_solved_by(&exe, reg) {
+            failures += 1;
+        }
+    }
+    assert!(failures > 0, "every split generalized");
+
+    let (all, none) = spec.split_train_test(1.0, &mut SmallRng::seed_from_u64(0));
+    assert_eq!((all, none.len()), (spec.clone(), 0));
+}